@@ -0,0 +1,371 @@
+use glob::Pattern;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yari_sys::{Context, Renderer, YariError, YrValue};
+
+/// Output format of the corpus results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorpusFormat {
+    Csv,
+    Json,
+    Table,
+}
+
+impl FromStr for CorpusFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(CorpusFormat::Csv),
+            "json" => Ok(CorpusFormat::Json),
+            "table" => Ok(CorpusFormat::Table),
+            _ => Err(format!("unknown corpus format '{}'", s)),
+        }
+    }
+}
+
+/// Results of the expressions for one sample.
+#[derive(Debug)]
+pub struct Row {
+    pub sample: PathBuf,
+    /// Result of every expression, `Err` if the sample could not be loaded
+    pub values: Result<Vec<Result<YrValue, YariError>>, YariError>,
+}
+
+/// Files in the `paths`, directories are walked in the order of the file names.
+///
+/// Files found in directories are kept only if their name matches one of the `patterns` (all
+/// are kept if there is none), files passed directly are always kept. Directories which can
+/// not be read are reported and skipped.
+pub fn collect_samples(paths: &[PathBuf], patterns: &[Pattern], recursive: bool) -> Vec<PathBuf> {
+    let mut samples = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, patterns, recursive, &mut samples);
+        } else {
+            samples.push(path.clone());
+        }
+    }
+    samples
+}
+
+fn walk_dir(dir: &Path, patterns: &[Pattern], recursive: bool, samples: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("{}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            if recursive {
+                walk_dir(&path, patterns, recursive, samples);
+            }
+        } else if is_matching(&path, patterns) {
+            samples.push(path);
+        }
+    }
+}
+
+/// Check if the file name matches one of the `patterns`, every name matches an empty list.
+fn is_matching(path: &Path, patterns: &[Pattern]) -> bool {
+    if patterns.is_empty() {
+        return true;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    patterns.iter().any(|pattern| pattern.matches(&name))
+}
+
+/// Evaluate the `expressions` for every sample, one sample per context at a time.
+///
+/// Every context is moved to its own thread, which takes the next sample and sets it as the
+/// context sample, so the rules are compiled only once per thread. Rows are returned in the
+/// order of the `samples`.
+pub fn evaluate(contexts: Vec<Context>, samples: &[PathBuf], expressions: &[String]) -> Vec<Row> {
+    let next = AtomicUsize::new(0);
+    let next = &next;
+
+    let mut rows = Vec::new();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = contexts
+            .into_iter()
+            .map(|mut context| {
+                scope.spawn(move || {
+                    let mut rows = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(sample) = samples.get(index) else {
+                            break;
+                        };
+                        let values = evaluate_sample(&mut context, sample, expressions);
+                        let sample = sample.clone();
+                        rows.push((index, Row { sample, values }));
+                    }
+                    rows
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            rows.extend(worker.join().expect("corpus worker panicked"));
+        }
+    });
+
+    rows.sort_by_key(|(index, _)| *index);
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+fn evaluate_sample(
+    context: &mut Context,
+    sample: &Path,
+    expressions: &[String],
+) -> Result<Vec<Result<YrValue, YariError>>, YariError> {
+    context.set_sample(sample)?;
+    Ok(expressions
+        .iter()
+        .map(|expression| context.eval(expression))
+        .collect())
+}
+
+/// Format the rows with one line per sample (one per row of the table).
+pub fn format_rows(rows: &[Row], expressions: &[String], format: CorpusFormat) -> String {
+    match format {
+        CorpusFormat::Csv => format_csv(rows, expressions),
+        CorpusFormat::Json => format_json(rows, expressions),
+        CorpusFormat::Table => format_table(rows, expressions),
+    }
+}
+
+/// CSV with a column per expression, errors of the row are joined in the last column.
+fn format_csv(rows: &[Row], expressions: &[String]) -> String {
+    let mut header = vec!["sample".to_string()];
+    header.extend(expressions.iter().map(|e| csv_field(e)));
+    header.push("error".to_string());
+
+    let mut lines = vec![header.join(",")];
+    for row in rows {
+        let mut fields = vec![csv_field(&row.sample.to_string_lossy())];
+        let mut errors = Vec::new();
+        match &row.values {
+            Ok(values) => {
+                for (expression, value) in expressions.iter().zip(values) {
+                    match value {
+                        Ok(value) => fields.push(csv_field(&plain_value(value))),
+                        Err(e) => {
+                            fields.push(String::new());
+                            errors.push(format!("{}: {}", expression, e));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                fields.extend(expressions.iter().map(|_| String::new()));
+                errors.push(e.to_string());
+            }
+        }
+        fields.push(csv_field(&errors.join("; ")));
+        lines.push(fields.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// JSON array with an object per sample.
+///
+/// Values are keyed by the expression, expressions which failed are in `errors` instead.
+/// Samples which could not be loaded have only the `error`.
+fn format_json(rows: &[Row], expressions: &[String]) -> String {
+    let objects: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let sample = row.sample.to_string_lossy();
+            let values = match &row.values {
+                Ok(values) => values,
+                Err(e) => return json!({"sample": sample, "error": e.to_string()}),
+            };
+
+            let mut fields = Map::new();
+            let mut errors = Map::new();
+            for (expression, value) in expressions.iter().zip(values) {
+                match value {
+                    Ok(value) => fields.insert(expression.clone(), json!(value)),
+                    Err(e) => errors.insert(expression.clone(), json!(e.to_string())),
+                };
+            }
+            json!({"sample": sample, "values": fields, "errors": errors})
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&objects).expect("corpus rows are serializable") + "\n"
+}
+
+/// Table with aligned columns, composite values are shown as JSON.
+fn format_table(rows: &[Row], expressions: &[String]) -> String {
+    let renderer = Renderer::default();
+    let mut table = vec![std::iter::once("sample".to_string())
+        .chain(expressions.iter().cloned())
+        .collect::<Vec<_>>()];
+
+    for row in rows {
+        let mut cells = vec![row.sample.to_string_lossy().into_owned()];
+        match &row.values {
+            Ok(values) => cells.extend(values.iter().map(|value| match value {
+                Ok(value @ (YrValue::Array(_) | YrValue::Dictionary(_))) => {
+                    json!(value).to_string()
+                }
+                Ok(value @ YrValue::Structure(Some(_))) => json!(value).to_string(),
+                Ok(value) => renderer.render(value),
+                Err(e) => renderer.render_error(e),
+            })),
+            Err(e) => cells.push(renderer.render_error(e)),
+        }
+        table.push(cells);
+    }
+
+    // Error of the sample which could not be loaded spans the columns of the expressions
+    let mut widths = vec![0; expressions.len() + 1];
+    for cells in &table {
+        let columns = if cells.len() == widths.len() {
+            cells.len()
+        } else {
+            1
+        };
+        for (width, cell) in widths.iter_mut().zip(cells).take(columns) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut res = String::new();
+    for cells in &table {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        res.push_str(line.join("  ").trim_end());
+        res.push('\n');
+    }
+    res
+}
+
+/// Value as a CSV field, undefined values are empty and composite values are JSON.
+fn plain_value(value: &YrValue) -> String {
+    match value {
+        _ if value.is_undefined() => String::new(),
+        YrValue::Integer(i) => i.to_string(),
+        YrValue::Float(f) => f.to_string(),
+        YrValue::String(Some(s)) => s.clone(),
+        YrValue::Bytes(b) => YrValue::escape_bytes(b),
+        _ => json!(value).to_string(),
+    }
+}
+
+/// Quote the CSV field if it contains a separator, quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row {
+                sample: PathBuf::from("a.exe"),
+                values: Ok(vec![
+                    Ok(YrValue::Integer(4)),
+                    Ok(YrValue::String(Some("x,\"y\"".to_string()))),
+                ]),
+            },
+            Row {
+                sample: PathBuf::from("b.exe"),
+                values: Ok(vec![Ok(YrValue::Integer(12)), Err(YariError::EvalError)]),
+            },
+            Row {
+                sample: PathBuf::from("c.exe"),
+                values: Err(YariError::SampleError("cannot read".to_string())),
+            },
+        ]
+    }
+
+    fn expressions() -> Vec<String> {
+        vec!["pe.number_of_sections".to_string(), "s".to_string()]
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("pe.number_of_sections"), "pe.number_of_sections");
+        assert_eq!(
+            csv_field("math.entropy(0, filesize)"),
+            "\"math.entropy(0, filesize)\""
+        );
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn test_is_matching() {
+        let patterns = [
+            Pattern::new("*.exe").unwrap(),
+            Pattern::new("*.dll").unwrap(),
+        ];
+        assert!(is_matching(Path::new("dir/a.exe"), &patterns));
+        assert!(is_matching(Path::new("b.dll"), &patterns));
+        assert!(!is_matching(Path::new("exe/c.txt"), &patterns));
+        assert!(is_matching(Path::new("c.txt"), &[]));
+    }
+
+    #[test]
+    fn test_format_csv() {
+        let csv = format_rows(&rows(), &expressions(), CorpusFormat::Csv);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "sample,pe.number_of_sections,s,error");
+        assert_eq!(lines[1], "a.exe,4,\"x,\"\"y\"\"\",");
+        assert_eq!(lines[2], "b.exe,12,,s: cannot evaluate the expression");
+        assert!(lines[3].starts_with("c.exe,,,"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let json = format_rows(&rows(), &expressions(), CorpusFormat::Json);
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            json[0],
+            json!({
+                "sample": "a.exe",
+                "values": {"pe.number_of_sections": 4, "s": "x,\"y\""},
+                "errors": {}
+            })
+        );
+        assert_eq!(
+            json[1],
+            json!({
+                "sample": "b.exe",
+                "values": {"pe.number_of_sections": 12},
+                "errors": {"s": "cannot evaluate the expression"}
+            })
+        );
+        assert_eq!(json[2]["sample"], "c.exe");
+        assert!(json[2]["error"].is_string());
+        assert!(json[2].get("values").is_none());
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_rows(&rows(), &expressions(), CorpusFormat::Table);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "sample  pe.number_of_sections  s");
+        assert_eq!(lines[1], "a.exe   4 (0x4)                \"x,\\\"y\\\"\"");
+        assert!(lines[2].ends_with("error: cannot evaluate the expression"));
+        assert!(lines[3].starts_with("c.exe   error: "));
+    }
+}
@@ -0,0 +1,414 @@
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hint, Hinter};
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::Helper;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::str::FromStr;
+use yari_sys::dump::ObjectKind;
+use yari_sys::{Context, Module, Schema, MODULES};
+
+use crate::SHELL_COMMANDS;
+
+/// Signature of the called function, shown after the cursor but never inserted into the line.
+pub struct SignatureHint(String);
+
+impl Hint for SignatureHint {
+    fn display(&self) -> &str {
+        &self.0
+    }
+
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Completion, hints and validation of the interactive shell input.
+pub struct ShellHelper {
+    context: Rc<RefCell<Context>>,
+    /// Schemas do not depend on the sample, so they are kept for the whole session
+    schemas: RefCell<HashMap<Module, Schema>>,
+}
+
+impl ShellHelper {
+    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+        ShellHelper {
+            context,
+            schemas: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Declaration of the symbol on the `path` (e.g. `pe.sections[0].name`).
+    ///
+    /// The module is imported when its schema is needed for the first time.
+    fn resolve_with<R>(&self, path: &[&str], f: impl FnOnce(&Schema) -> R) -> Option<R> {
+        let (first, rest) = path.split_first()?;
+        let module = Module::from_str(first).ok()?;
+
+        let mut schemas = self.schemas.borrow_mut();
+        let mut schema: &Schema = match schemas.entry(module) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(self.context.borrow_mut().module_schema(module).ok()?)
+            }
+        };
+        for segment in rest {
+            let (name, indexes) = match segment.find('[') {
+                Some(i) => (&segment[..i], segment[i..].matches('[').count()),
+                None => (*segment, 0),
+            };
+            schema = schema.member(name)?;
+            for _ in 0..indexes {
+                schema = schema.item.as_deref()?;
+            }
+        }
+        Some(f(schema))
+    }
+
+    /// Members of the declared structure which start with the `partial` name.
+    fn schema_candidates(&self, parent: &[&str], partial: &str) -> Vec<(String, String)> {
+        self.resolve_with(parent, |schema| {
+            schema
+                .members
+                .iter()
+                .filter(|member| member.name.starts_with(partial))
+                .map(|member| {
+                    let suffix = match member.kind {
+                        ObjectKind::Function => "(",
+                        _ => "",
+                    };
+                    (member.name.clone(), format!("{}{}", member.name, suffix))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Next path component of the cached objects which start with the `token`.
+    ///
+    /// Unlike the schema the objects cache holds array indexes and dictionary keys.
+    fn cache_candidates(&self, token: &str) -> Vec<String> {
+        let context = self.context.borrow();
+        context
+            .object_paths()
+            .filter(|path| path.len() > token.len() && path.starts_with(token))
+            .map(|path| {
+                let rest = &path[token.len()..];
+                let end = rest
+                    .char_indices()
+                    .skip(1)
+                    .find(|(_, c)| *c == '.' || *c == '[')
+                    .map_or(rest.len(), |(i, _)| i);
+                format!("{}{}", token, &rest[..end])
+            })
+            .collect()
+    }
+}
+
+/// Start of the symbol path which ends at `pos`.
+fn token_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '"'))
+        .last()
+        .map_or(pos, |(i, _)| i)
+}
+
+/// Split the symbol path on dots which are not part of a dictionary key.
+fn split_path(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '.' if !in_string => {
+                segments.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+/// Human readable type of the function argument format character.
+fn argument_type(format: char) -> Cow<'static, str> {
+    match format {
+        'i' => "integer".into(),
+        'f' => "float".into(),
+        's' => "string".into(),
+        'r' => "regexp".into(),
+        c => c.to_string().into(),
+    }
+}
+
+/// Check that parentheses, brackets, string literals and regular expressions are closed.
+fn check_balanced(input: &str) -> Result<(), String> {
+    let mut stack = Vec::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '/' if c == '"' || input[..i].trim_end().ends_with("matches") => {
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        closed = true;
+                        break;
+                    }
+                }
+                if !closed {
+                    return Err(match c {
+                        '"' => "unterminated string".to_string(),
+                        _ => "unterminated regular expression".to_string(),
+                    });
+                }
+            }
+            '(' | '[' => stack.push(c),
+            ')' | ']' => {
+                let expected = if c == ')' { '(' } else { '[' };
+                if stack.pop() != Some(expected) {
+                    return Err(format!("unexpected '{}'", c));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match stack.pop() {
+        Some(c) => Err(format!("unclosed '{}'", c)),
+        None => Ok(()),
+    }
+}
+
+/// Position of the innermost parenthesis which is not closed before `pos`.
+fn open_call(line: &str, pos: usize) -> Option<usize> {
+    let mut stack = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line[..pos].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' if !in_string => stack.push(i),
+            ')' if !in_string => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    stack.pop()
+}
+
+/// Names of the shell commands, or the modules for `:dump`, which start with the word at `pos`.
+fn command_candidates(line: &str, pos: usize) -> (usize, Vec<Pair>) {
+    let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let partial = &line[start..pos];
+    let names: Vec<String> = match line[..start].split_whitespace().collect::<Vec<_>>()[..] {
+        [] => SHELL_COMMANDS
+            .iter()
+            .map(|(name, _, _)| name.to_string())
+            .collect(),
+        [":load"] => vec!["sample".to_string(), "rules".to_string()],
+        [":dump"] => MODULES.iter().map(|module| module.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    let pairs = names
+        .into_iter()
+        .filter(|name| name.starts_with(partial))
+        .map(|name| Pair {
+            display: name.clone(),
+            replacement: name,
+        })
+        .collect();
+    (start, pairs)
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        if line.trim_start().starts_with(':') {
+            return Ok(command_candidates(line, pos));
+        }
+
+        let start = token_start(line, pos);
+        let token = &line[start..pos];
+        let segments = split_path(token);
+
+        // Candidate replacements of the whole token with their displayed names
+        let mut candidates = BTreeMap::new();
+        if let [partial] = segments[..] {
+            for module in MODULES {
+                let name = module.to_string();
+                if name.starts_with(partial) {
+                    candidates.insert(name.clone(), name);
+                }
+            }
+        } else if let Some((partial, parent)) = segments.split_last() {
+            let base = &token[..token.len() - partial.len()];
+            for (name, replacement) in self.schema_candidates(parent, partial) {
+                candidates.insert(format!("{}{}", base, replacement), name);
+            }
+            for replacement in self.cache_candidates(token) {
+                // Functions are already completed from the schema including the parenthesis
+                if candidates.contains_key(&format!("{}(", replacement)) {
+                    continue;
+                }
+                let name = split_path(&replacement).last().unwrap_or(&"").to_string();
+                candidates.entry(replacement).or_insert(name);
+            }
+        }
+
+        let pairs = candidates
+            .into_iter()
+            .map(|(replacement, display)| Pair {
+                display,
+                replacement,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = SignatureHint;
+
+    /// Overloads of the function whose arguments are being typed.
+    fn hint(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> Option<SignatureHint> {
+        if pos < line.len() || line.trim_start().starts_with(':') {
+            return None;
+        }
+
+        let open = open_call(line, pos)?;
+        let path = &line[token_start(line, open)..open];
+        let segments = split_path(path);
+        let name = segments.last()?.to_string();
+
+        self.resolve_with(&segments, |schema| {
+            if schema.kind != ObjectKind::Function {
+                return None;
+            }
+            let overloads: Vec<String> = schema
+                .prototypes
+                .iter()
+                .map(|prototype| {
+                    let arguments: Vec<_> = prototype.chars().map(argument_type).collect();
+                    format!("{}({})", name, arguments.join(", "))
+                })
+                .collect();
+            let ret = schema
+                .return_kind
+                .map_or("undefined".to_string(), |kind| kind.to_string());
+            Some(SignatureHint(format!(
+                "  {} -> {}",
+                overloads.join(" | "),
+                ret
+            )))
+        })
+        .flatten()
+    }
+}
+
+impl Highlighter for ShellHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        // Dim the hint, so it is not mistaken for the input
+        Cow::Owned(format!("\x1b[2m{}\x1b[0m", hint))
+    }
+}
+
+impl Validator for ShellHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+        if input.trim_start().starts_with(':') {
+            return Ok(ValidationResult::Valid(None));
+        }
+
+        Ok(match check_balanced(input) {
+            Ok(()) => ValidationResult::Valid(None),
+            Err(e) => ValidationResult::Invalid(Some(format!("  <- {}", e))),
+        })
+    }
+}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_start() {
+        assert_eq!(token_start("pe.number_of_sections", 21), 0);
+        assert_eq!(token_start("1 + math.entropy(0, file", 24), 20);
+        assert_eq!(token_start("pe.version_info[\"Comp", 21), 0);
+        assert_eq!(token_start("r|$s", 4), 3);
+    }
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(split_path("pe"), ["pe"]);
+        assert_eq!(split_path("pe.sections[0].na"), ["pe", "sections[0]", "na"]);
+        assert_eq!(
+            split_path("pe.version_info[\"a.b\"]."),
+            ["pe", "version_info[\"a.b\"]", ""]
+        );
+    }
+
+    #[test]
+    fn test_check_balanced() {
+        assert_eq!(check_balanced("math.entropy(0, filesize)"), Ok(()));
+        assert_eq!(check_balanced("pe.sections[0].name == \")(\""), Ok(()));
+        assert_eq!(check_balanced("\"a\\\"b\" matches /a(b/"), Ok(()));
+        assert_eq!(
+            check_balanced("math.entropy(0, filesize"),
+            Err("unclosed '('".to_string())
+        );
+        assert_eq!(
+            check_balanced("pe.sections[0)"),
+            Err("unexpected ')'".to_string())
+        );
+        assert_eq!(
+            check_balanced("\"abc"),
+            Err("unterminated string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_command_candidates() {
+        let names = |line: &str| -> Vec<String> {
+            command_candidates(line, line.len())
+                .1
+                .into_iter()
+                .map(|pair| pair.replacement)
+                .collect()
+        };
+        assert_eq!(names(":r"), [":reload", ":rules", ":rule"]);
+        assert_eq!(names(":load s"), ["sample"]);
+        assert_eq!(command_candidates(":dump p", 7).0, 6);
+        assert!(names(":dump p").contains(&"pe".to_string()));
+        assert!(names(":rule r ").is_empty());
+    }
+
+    #[test]
+    fn test_open_call() {
+        assert_eq!(open_call("pe.exports(", 11), Some(10));
+        assert_eq!(open_call("math.entropy(0, hash.md5(\"(\"", 28), Some(24));
+        assert_eq!(open_call("pe.exports(\"a\")", 15), None);
+    }
+}
@@ -1,13 +1,442 @@
-use clap::{command, Arg, ArgAction, Command};
-use color_eyre::eyre::{bail, Context, Result};
+mod corpus;
+mod helper;
+
+use clap::{command, Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use corpus::CorpusFormat;
+use glob::Pattern;
+use helper::ShellHelper;
 use log::LevelFilter;
 use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use serde_json::json;
+use std::cell::RefCell;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::str::FromStr;
-use yari_sys::{ContextBuilder, Module};
+use yari_sys::atoms::StringAtoms;
+use yari_sys::{
+    ContextBuilder, DumpFormat, ExternalVariable, Module, Profile, Renderer, Rule, StringMatch,
+    MODULES,
+};
+
+/// Rule declaration line with modifiers and tags (e.g. `private rule default:r : tag`).
+fn rule_header(rule: &Rule) -> String {
+    let mut header = String::new();
+    if rule.is_private {
+        header.push_str("private ");
+    }
+    if rule.is_global {
+        header.push_str("global ");
+    }
+    header.push_str("rule ");
+    header.push_str(&rule.qualified_name());
+    if !rule.tags.is_empty() {
+        header.push_str(" : ");
+        header.push_str(&rule.tags.join(" "));
+    }
+    header
+}
+
+/// Print the rule details, metadata and strings with the number of matches.
+fn print_rule(rule: &Rule) {
+    println!("{}", rule_header(rule));
+    if !rule.metas.is_empty() {
+        println!("  meta:");
+        for (name, value) in &rule.metas {
+            println!("    {} = {}", name, value);
+        }
+    }
+    if !rule.strings.is_empty() {
+        println!("  strings:");
+        print_rule_strings(rule, "    ");
+    }
+}
+
+/// Print the strings of the rule with their modifiers and the number of matches.
+fn print_rule_strings(rule: &Rule, indent: &str) {
+    for string in &rule.strings {
+        let mut line = format!("{}{}", indent, string.identifier);
+        if !string.modifiers.is_empty() {
+            line.push_str(&format!(" ({})", string.modifiers.join(" ")));
+        }
+        println!("{}: {} match(es)", line, string.matches);
+    }
+}
+
+/// Print the string matches as a table, matched data are escaped.
+fn print_string_matches(matches: &[StringMatch]) {
+    println!(
+        "{:>6} {:>18} {:>18} {:>8} {:>7} {:>5}  data",
+        "#", "offset", "base", "length", "xor_key", "chain"
+    );
+    for (i, m) in matches.iter().enumerate() {
+        let data: String = m
+            .data
+            .iter()
+            .flat_map(|b| std::ascii::escape_default(*b))
+            .map(char::from)
+            .collect();
+        let xor_key = m
+            .xor_key
+            .map_or_else(|| "-".to_string(), |key| format!("{:#04x}", key));
+        println!(
+            "{:>6} {:>#18x} {:>#18x} {:>8} {:>7} {:>5}  {}",
+            i + 1,
+            m.offset,
+            m.base,
+            m.length,
+            xor_key,
+            m.chain_length,
+            data
+        );
+    }
+}
+
+/// Print the atoms of the strings, atoms with quality below the warning threshold are marked.
+fn print_atoms(strings: &[StringAtoms]) {
+    for string in strings {
+        println!("{}", string.identifier);
+        println!(
+            "  {:>6}  {:<28} {:>7} {:>8}",
+            "offset", "atom", "quality", "hits"
+        );
+        for atom in &string.atoms {
+            println!(
+                "  {:>6}  {:<28} {:>7} {:>8}{}",
+                atom.backtrack,
+                atom.to_string(),
+                atom.quality,
+                atom.hits,
+                if atom.is_slow() { "  (slow)" } else { "" }
+            );
+        }
+    }
+}
+
+/// Print the profiled rules and strings as tables, the most expensive go first.
+fn print_profile(profile: &Profile) {
+    println!("{:>12} {:>10} {:>14}  rule", "cost", "atoms", "match time");
+    for rule in &profile.rules {
+        let cost = rule
+            .cost
+            .map_or_else(|| "-".to_string(), |cost| cost.to_string());
+        println!(
+            "{:>12} {:>10} {:>14.3?}  {}",
+            cost, rule.atom_matches, rule.match_time, rule.rule
+        );
+    }
+
+    println!();
+    println!("{:>10} {:>14}  string", "atoms", "match time");
+    for string in &profile.strings {
+        println!(
+            "{:>10} {:>14.3?}  {}{}",
+            string.atom_matches, string.match_time, string.rule, string.identifier
+        );
+    }
+}
+
+/// Commands of the interactive shell with their usage and description.
+const SHELL_COMMANDS: &[(&str, &str, &str)] = &[
+    (
+        ":load",
+        ":load sample|rules PATH",
+        "replace the sample or the rules",
+    ),
+    (
+        ":reload",
+        ":reload",
+        "rebuild the context, rules and sample are read again",
+    ),
+    (
+        ":dump",
+        ":dump MODULE [PATH]",
+        "dump the module structure, PATH.json or PATH.yaml for other formats",
+    ),
+    (
+        ":modules",
+        ":modules",
+        "list the modules, the ones imported by the rules are marked",
+    ),
+    (":rules", ":rules", "list the rules"),
+    (":rule", ":rule RULE", "print the rule details"),
+    (
+        ":strings",
+        ":strings RULE",
+        "print the rule strings with the number of matches",
+    ),
+    (
+        ":matches",
+        ":matches RULE $STRING",
+        "print the string matches",
+    ),
+    (
+        ":atoms",
+        ":atoms RULE",
+        "print the atoms of the rule strings",
+    ),
+    (":explain", ":explain RULE", "explain the rule condition"),
+    (":full", ":full", "toggle rendering of all array items"),
+    (":help", ":help", "print this help"),
+    (":quit", ":quit", "exit the shell"),
+];
+
+/// Arguments the context is built from, kept to rebuild the context from the shell.
+#[derive(Debug, Clone, Default)]
+struct ContextOptions {
+    rule_file: Option<String>,
+    compiled_rules: bool,
+    sample: Option<String>,
+    /// Sample read from the standard input, it can not be read again on reload
+    sample_bytes: Option<Vec<u8>>,
+    module_data: Vec<(Module, String)>,
+    externals: Vec<(String, ExternalVariable)>,
+    profiling: bool,
+}
+
+impl ContextOptions {
+    /// Build a new context and print its diagnostics.
+    fn build(&self) -> Result<yari_sys::Context> {
+        let context = self.build_quiet()?;
+        for diagnostic in context.diagnostics() {
+            eprintln!("{}", diagnostic);
+        }
+        Ok(context)
+    }
+
+    /// Build a new context without printing its diagnostics.
+    fn build_quiet(&self) -> Result<yari_sys::Context> {
+        let mut builder = match &self.rule_file {
+            Some(rule_file) if self.compiled_rules => {
+                ContextBuilder::default().with_compiled_rules(rule_file)
+            }
+            rule_file => ContextBuilder::default().with_rule_file(rule_file.as_ref()),
+        };
+
+        builder = match &self.sample_bytes {
+            Some(bytes) => builder.with_sample_bytes(bytes.clone()),
+            None => builder.with_sample(self.sample.as_ref()),
+        };
+
+        for (module, data) in &self.module_data {
+            builder = builder.with_module_data(*module, data);
+        }
+
+        for (name, value) in &self.externals {
+            builder = builder.with_external(name, value.clone());
+        }
+
+        builder
+            .with_profiling(self.profiling)
+            .build()
+            .context("Failed to create YARI context")
+    }
+}
+
+/// Whether the shell continues after the command.
+#[derive(Debug, PartialEq, Eq)]
+enum ShellFlow {
+    Continue,
+    Quit,
+}
+
+/// Execute the interactive shell command starting with `:`.
+///
+/// Commands which load new rules rebuild the context, the current one is kept if it fails.
+fn shell_command(
+    context: &RefCell<yari_sys::Context>,
+    options: &mut ContextOptions,
+    renderer: &mut Renderer,
+    line: &str,
+) -> Result<ShellFlow> {
+    let (command, argument) = match line.split_once(char::is_whitespace) {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        ":load" => match argument.split_once(char::is_whitespace) {
+            Some(("sample", path)) => {
+                let path = path.trim();
+                context.borrow_mut().set_sample(path)?;
+                options.sample = Some(path.to_string());
+                options.sample_bytes = None;
+            }
+            Some(("rules", path)) => {
+                let path = path.trim();
+                if !Path::new(path).is_file() {
+                    bail!("rule file '{}' not found", path);
+                }
+                let new_options = ContextOptions {
+                    rule_file: Some(path.to_string()),
+                    compiled_rules: false,
+                    ..options.clone()
+                };
+                // The current context is kept if the rules can not be compiled
+                *context.borrow_mut() = new_options.build()?;
+                *options = new_options;
+            }
+            _ => bail!("usage: :load sample|rules PATH"),
+        },
+        ":reload" => {
+            *context.borrow_mut() = options.build()?;
+        }
+        ":dump" => {
+            let (module, path) = match argument.split_once(char::is_whitespace) {
+                Some((module, path)) => (module, Some(path.trim())),
+                None => (argument, None),
+            };
+            let module = Module::from_str(module)?;
+            let dump = context.borrow_mut().dump_module(module)?;
+            match path {
+                Some(path) => {
+                    let format = match Path::new(path).extension().and_then(|e| e.to_str()) {
+                        Some("json") => DumpFormat::Json,
+                        Some("yaml" | "yml") => DumpFormat::Yaml,
+                        _ => DumpFormat::Text,
+                    };
+                    std::fs::write(path, dump.format(format))
+                        .with_context(|| format!("cannot write the dump to '{}'", path))?;
+                }
+                None => println!("{}", renderer.render_node(&dump)),
+            }
+        }
+        ":modules" => {
+            let imports = context.borrow().imports();
+            for module in MODULES {
+                let name = module.to_string();
+                let mark = if imports.contains(&name) { "*" } else { " " };
+                println!("{} {}", mark, name);
+            }
+        }
+        ":rules" => {
+            for rule in context.borrow().rules() {
+                println!("{}", rule_header(&rule));
+            }
+        }
+        ":rule" => {
+            let Some(rule) = context.borrow().rule(argument) else {
+                bail!("rule '{}' not found", argument);
+            };
+            print_rule(&rule);
+        }
+        ":strings" => {
+            let Some(rule) = context.borrow().rule(argument) else {
+                bail!("rule '{}' not found", argument);
+            };
+            print_rule_strings(&rule, "");
+        }
+        ":matches" => {
+            let Some((rule, identifier)) = argument.split_once(char::is_whitespace) else {
+                bail!("usage: :matches RULE $STRING");
+            };
+            let matches = context
+                .borrow_mut()
+                .string_matches(rule, identifier.trim())?;
+            print_string_matches(&matches);
+        }
+        ":atoms" => {
+            print_atoms(&context.borrow().atoms(argument)?);
+        }
+        ":explain" => {
+            print!("{}", context.borrow_mut().explain(argument)?);
+        }
+        ":full" => {
+            renderer.full = !renderer.full;
+            let state = if renderer.full { "on" } else { "off" };
+            println!("full output {}", state);
+        }
+        ":help" => {
+            for (_, usage, description) in SHELL_COMMANDS {
+                println!("{:<26} {}", usage, description);
+            }
+        }
+        ":quit" | ":q" => return Ok(ShellFlow::Quit),
+        _ => bail!("unknown command '{}', see :help", command),
+    }
+
+    Ok(ShellFlow::Continue)
+}
+
+/// Exit code of the evaluation if an assertion is false.
+const EXIT_ASSERTION_FAILED: i32 = 1;
+
+/// Exit code of the evaluation if an expression can not be evaluated.
+const EXIT_EVAL_ERROR: i32 = 2;
+
+/// Expressions of the script, one per line, empty lines and `//` comments are skipped.
+fn script_expressions(script: &str) -> impl Iterator<Item = &str> {
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+}
+
+/// Evaluate the `expressions` and print the results, returns the exit code.
+///
+/// Evaluation continues after a failed expression, so every result is printed. With `assert`
+/// the values which are not true make the exit code `EXIT_ASSERTION_FAILED`, failed evaluation
+/// takes precedence.
+fn evaluate(
+    context: &mut yari_sys::Context,
+    expressions: &[String],
+    json: bool,
+    assert: bool,
+    renderer: &Renderer,
+) -> i32 {
+    let mut exit_code = 0;
+    let mut results = Vec::new();
+
+    for expression in expressions {
+        let res = context.eval(expression);
+        let status = match &res {
+            Ok(value) if assert => match bool::try_from(value.clone()) {
+                Ok(true) => 0,
+                Ok(false) => EXIT_ASSERTION_FAILED,
+                Err(e) => {
+                    eprintln!("{}: {}", expression, renderer.render_error(&e));
+                    EXIT_EVAL_ERROR
+                }
+            },
+            Ok(_) => 0,
+            Err(_) => EXIT_EVAL_ERROR,
+        };
+        exit_code = exit_code.max(status);
+
+        if json {
+            results.push(match &res {
+                Ok(value) => json!({"expression": expression, "value": value}),
+                Err(e) => json!({"expression": expression, "error": e.to_string()}),
+            });
+            continue;
+        }
+
+        match res {
+            Ok(value) => println!("{}", renderer.render(&value)),
+            Err(e) => eprintln!("{}: {}", expression, renderer.render_error(&e)),
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::Value::Array(results));
+    }
+    exit_code
+}
 
 /// Spawn interactive shell
-fn interactive(context: &mut yari_sys::Context) -> Result<()> {
-    let mut rl = rustyline::DefaultEditor::new().context("cannot create an editor")?;
+fn interactive(
+    context: yari_sys::Context,
+    mut options: ContextOptions,
+    mut renderer: Renderer,
+) -> Result<()> {
+    // Context is shared with the helper, which completes the symbols while the line is edited
+    let context = Rc::new(RefCell::new(context));
+    let mut rl: Editor<ShellHelper, DefaultHistory> =
+        Editor::new().context("cannot create an editor")?;
+    rl.set_helper(Some(ShellHelper::new(Rc::clone(&context))));
 
     loop {
         let readline = rl.readline(">> ");
@@ -15,12 +444,22 @@ fn interactive(context: &mut yari_sys::Context) -> Result<()> {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
 
-                match context.eval(&line) {
+                if line.trim_start().starts_with(':') {
+                    match shell_command(&context, &mut options, &mut renderer, line.trim()) {
+                        Ok(ShellFlow::Quit) => break,
+                        Ok(ShellFlow::Continue) => {}
+                        Err(e) => println!("{}", renderer.render_error(&e)),
+                    }
+                    continue;
+                }
+
+                let res = context.borrow_mut().eval(&line);
+                match res {
                     Ok(res_obj) => {
-                        println!("{:?}", res_obj);
+                        println!("{}", renderer.render(&res_obj));
                     }
                     Err(e) => {
-                        println!("{:?}", e);
+                        println!("{}", renderer.render_error(&e));
                     }
                 }
             }
@@ -40,6 +479,76 @@ fn interactive(context: &mut yari_sys::Context) -> Result<()> {
     Ok(())
 }
 
+/// Print the schema of the `MODULE` or of all the modules as JSON.
+fn print_schema(sub_matches: &ArgMatches) -> Result<()> {
+    // Schema does not depend on the sample nor the rules
+    let mut context = ContextOptions::default().build()?;
+    match sub_matches.get_one::<String>("MODULE") {
+        Some(module) => {
+            let module = Module::from_str(module)?;
+            let schema = context.module_schema(module)?;
+            println!("{}", serde_json::to_string(&schema)?);
+        }
+        None => {
+            let schemas = MODULES
+                .iter()
+                .map(|module| context.module_schema(*module))
+                .collect::<Result<Vec<_>, _>>()?;
+            println!("{}", serde_json::to_string(&schemas)?);
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate the expressions on every sample of the corpus and print them as rows.
+fn evaluate_corpus(sub_matches: &ArgMatches, options: ContextOptions) -> Result<()> {
+    let paths: Vec<PathBuf> = sub_matches
+        .get_many::<String>("PATH")
+        .unwrap()
+        .map(PathBuf::from)
+        .collect();
+    let expressions: Vec<String> = sub_matches
+        .get_many::<String>("EVAL")
+        .unwrap()
+        .cloned()
+        .collect();
+    let patterns = sub_matches
+        .get_many::<String>("GLOB")
+        .unwrap_or_default()
+        .map(|pattern| Pattern::new(pattern).with_context(|| pattern.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    let format = CorpusFormat::from_str(sub_matches.get_one::<String>("FORMAT").unwrap())
+        .map_err(|e| eyre!(e))?;
+
+    let samples = corpus::collect_samples(&paths, &patterns, sub_matches.get_flag("RECURSIVE"));
+    let jobs = match sub_matches.get_one::<usize>("JOBS") {
+        Some(jobs) => *jobs,
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    // Samples are set by the workers, every one of them has its own context
+    let corpus_options = ContextOptions {
+        rule_file: sub_matches
+            .get_one::<String>("RULES")
+            .cloned()
+            .or(options.rule_file),
+        sample: None,
+        sample_bytes: None,
+        profiling: false,
+        ..options
+    };
+    let contexts = (0..jobs.clamp(1, samples.len().max(1)))
+        .map(|i| match i {
+            0 => corpus_options.build(),
+            _ => corpus_options.build_quiet(),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let rows = corpus::evaluate(contexts, &samples, &expressions);
+    print!("{}", corpus::format_rows(&rows, &expressions, format));
+    Ok(())
+}
+
 fn main() -> Result<()> {
     // Setup logging
     env_logger::Builder::from_default_env()
@@ -50,6 +559,30 @@ fn main() -> Result<()> {
 
     // Declare command line arguments
     let matches = command!()
+        .arg(
+            Arg::new("COMPILED_RULES")
+                .short('C')
+                .long("compiled-rules")
+                .action(ArgAction::SetTrue)
+                .help("load compiled rules"),
+        )
+        .arg(
+            Arg::new("ASSERT")
+                .long("assert")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "exit with 1 if a value of the evaluated expressions is false \
+                    and with 2 if an expression can not be evaluated",
+                ),
+        )
+        .arg(
+            Arg::new("DEFINE")
+                .short('d')
+                .long("define")
+                .value_name("VAR=VALUE")
+                .action(ArgAction::Append)
+                .help("define external variable"),
+        )
         .arg(
             Arg::new("MODULE_DATA")
                 .short('x')
@@ -58,10 +591,32 @@ fn main() -> Result<()> {
                 .action(ArgAction::Append)
                 .help("pass FILE's content as extra data to MODULE"),
         )
+        .arg(
+            Arg::new("EVAL")
+                .short('e')
+                .long("eval")
+                .value_name("EXPR")
+                .action(ArgAction::Append)
+                .help("evaluate EXPR and print the result instead of starting the shell"),
+        )
+        .arg(
+            Arg::new("FORMAT")
+                .long("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .help("output format of the evaluated expressions"),
+        )
+        .arg(
+            Arg::new("FULL")
+                .long("full")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("render all array items instead of the first ones"),
+        )
         .arg(
             Arg::new("INPUT")
                 .value_name("FILE")
-                .help("FILE to scan")
+                .help("FILE to scan, use '-' to read it from the standard input")
                 .required(false),
         )
         .arg(
@@ -70,6 +625,22 @@ fn main() -> Result<()> {
                 .help("print license information")
                 .required(false),
         )
+        .arg(
+            Arg::new("PROFILE")
+                .long("profile")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "profile the scan and print the cost of the rules and strings \
+                    (the rule cost needs the profiling feature)",
+                ),
+        )
+        .arg(
+            Arg::new("SCRIPT")
+                .short('f')
+                .long("script")
+                .value_name("FILE")
+                .help("evaluate the expressions in FILE, one per line"),
+        )
         .arg(
             Arg::new("RULE_FILE")
                 .value_name("RULE")
@@ -79,7 +650,87 @@ fn main() -> Result<()> {
         .subcommand(
             Command::new("dump")
                 .about("Dump a module structure")
-                .arg(Arg::new("MODULE").help("module name").required(true)),
+                .arg(Arg::new("MODULE").help("module name").required(true))
+                .arg(
+                    Arg::new("FORMAT")
+                        .long("format")
+                        .value_parser(["pretty", "text", "json", "yaml"])
+                        .default_value("pretty")
+                        .help("output format"),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the declarations of module symbols as JSON")
+                .arg(
+                    Arg::new("MODULE")
+                        .help("module name, all modules when omitted")
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("corpus")
+                .about("Evaluate expressions for every sample in the directories")
+                .arg(
+                    Arg::new("PATH")
+                        .help("sample or directory of samples")
+                        .action(ArgAction::Append)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("EVAL")
+                        .short('e')
+                        .long("eval")
+                        .value_name("EXPR")
+                        .action(ArgAction::Append)
+                        .required(true)
+                        .help("expression to evaluate, one column per expression"),
+                )
+                .arg(
+                    Arg::new("RULES")
+                        .long("rules")
+                        .value_name("RULE")
+                        .help("FILE containing rules, compiled with -C"),
+                )
+                .arg(
+                    Arg::new("GLOB")
+                        .short('g')
+                        .long("glob")
+                        .value_name("PATTERN")
+                        .action(ArgAction::Append)
+                        .help("evaluate only files in directories whose name matches PATTERN"),
+                )
+                .arg(
+                    Arg::new("RECURSIVE")
+                        .short('r')
+                        .long("recursive")
+                        .action(ArgAction::SetTrue)
+                        .help("walk the subdirectories"),
+                )
+                .arg(
+                    Arg::new("FORMAT")
+                        .long("format")
+                        .value_parser(["table", "csv", "json"])
+                        .default_value("table")
+                        .help("output format"),
+                )
+                .arg(
+                    Arg::new("JOBS")
+                        .short('j')
+                        .long("jobs")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("number of samples evaluated in parallel, all CPUs by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("disasm")
+                .about("Disassemble the condition bytecode of compiled rules")
+                .arg(
+                    Arg::new("RULE")
+                        .help("rule to disassemble, all rules when omitted")
+                        .required(false),
+                ),
         )
         .get_matches();
 
@@ -88,33 +739,141 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    // Expressions are evaluated on the sample of the top-level context only
+    if matches.subcommand().is_some()
+        && (matches.contains_id("EVAL") || matches.contains_id("SCRIPT"))
+    {
+        bail!("'--eval' and '--script' can not be used with a subcommand");
+    }
+
     // Prepare the context
-    let input_file = matches.get_one::<String>("INPUT");
-    let rule_file = matches.get_one::<String>("RULE_FILE");
-    let mut builder = ContextBuilder::default()
-        .with_sample(input_file)
-        .with_rule_file(rule_file);
+    let mut options = ContextOptions {
+        rule_file: matches.get_one::<String>("RULE_FILE").cloned(),
+        compiled_rules: matches.get_flag("COMPILED_RULES"),
+        profiling: matches.get_flag("PROFILE"),
+        ..Default::default()
+    };
+
+    match matches.get_one::<String>("INPUT") {
+        Some(input_file) if input_file == "-" => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .lock()
+                .read_to_end(&mut bytes)
+                .context("cannot read the sample from the standard input")?;
+            options.sample_bytes = Some(bytes);
+        }
+        input_file => options.sample = input_file.cloned(),
+    }
 
     // Add the module data
     if let Some(modules_data) = matches.get_many::<String>("MODULE_DATA") {
         for module_data in modules_data {
-            let (module, data) = ContextBuilder::parse_module_data_str(module_data).unwrap();
-            builder = builder.with_module_data(module, &data);
+            let module_data = ContextBuilder::parse_module_data_str(module_data).unwrap();
+            options.module_data.push(module_data);
         }
     }
 
-    let mut context = builder.build().context("Failed to create YARI context")?;
+    // Define the external variables
+    if let Some(definitions) = matches.get_many::<String>("DEFINE") {
+        for definition in definitions {
+            let Some(external) = ContextBuilder::parse_external_variable_str(definition) else {
+                bail!(
+                    "external variable expected in format 'VAR=VALUE', got '{}'",
+                    definition
+                );
+            };
+            options.externals.push(external);
+        }
+    }
+
+    // Subcommands which do not use the sample of the top-level context
+    match matches.subcommand() {
+        Some(("schema", sub_matches)) => return print_schema(sub_matches),
+        Some(("corpus", sub_matches)) => return evaluate_corpus(sub_matches, options),
+        _ => {}
+    }
+
+    let mut context = options.build()?;
+
+    // Highlight the rendered values only if they are printed to the terminal
+    let renderer = Renderer::default()
+        .with_full(matches.get_flag("FULL"))
+        .with_color(std::io::stdout().is_terminal());
+
+    // Profile is printed before the expressions are evaluated or the shell is started
+    if let Some(profile) = context.profile() {
+        print_profile(profile);
+    }
+
+    // Evaluate the expressions passed on the command line instead of starting the shell
+    if matches.contains_id("EVAL") || matches.contains_id("SCRIPT") {
+        let mut expressions: Vec<String> = matches
+            .get_many::<String>("EVAL")
+            .map(|expressions| expressions.cloned().collect())
+            .unwrap_or_default();
+        if let Some(script) = matches.get_one::<String>("SCRIPT") {
+            let script = std::fs::read_to_string(script)
+                .with_context(|| format!("cannot read the script '{}'", script))?;
+            expressions.extend(script_expressions(&script).map(String::from));
+        }
+
+        let json = matches
+            .get_one::<String>("FORMAT")
+            .is_some_and(|f| f == "json");
+        let exit_code = evaluate(
+            &mut context,
+            &expressions,
+            json,
+            matches.get_flag("ASSERT"),
+            &renderer,
+        );
+        std::process::exit(exit_code);
+    }
 
     match matches.subcommand() {
         Some(("dump", sub_matches)) => {
             let module = Module::from_str(sub_matches.get_one::<String>("MODULE").unwrap())?;
-            context.dump_module(module)?;
+            let dump = context.dump_module(module)?;
+            match sub_matches.get_one::<String>("FORMAT").unwrap().as_str() {
+                "pretty" => println!("{}", renderer.render_node(&dump)),
+                format => {
+                    let format = DumpFormat::from_str(format).map_err(|e| eyre!(e))?;
+                    print!("{}", dump.format(format));
+                }
+            }
+        }
+        Some(("disasm", sub_matches)) => {
+            let rules = match sub_matches.get_one::<String>("RULE") {
+                Some(rule) => vec![rule.clone()],
+                None => context.rules().iter().map(Rule::qualified_name).collect(),
+            };
+            for rule in rules {
+                println!("{}:", rule);
+                for instruction in context.disassemble(&rule)? {
+                    println!("  {}", instruction);
+                }
+            }
         }
         _ => {
             // Start interactive shell
-            interactive(&mut context)?;
+            interactive(context, options, renderer)?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_expressions() {
+        let script = "pe.number_of_sections\n\n  // comment\n  filesize > 10  \n";
+        assert_eq!(
+            script_expressions(script).collect::<Vec<_>>(),
+            ["pe.number_of_sections", "filesize > 10"]
+        );
+    }
+}
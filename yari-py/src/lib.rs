@@ -1,14 +1,28 @@
+use indexmap::IndexMap;
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
+use pyo3::types::PyBool;
+use pyo3::types::PyBytes;
 use pyo3::types::PyDict;
+use pyo3::types::PyFloat;
+use pyo3::types::PyLong;
 use pyo3::types::PyString;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
+use yari_sys::dump::ObjectKind;
+use yari_sys::error::Diagnostic;
+use yari_sys::rules::MetaValue;
 use yari_sys::Context as YARIContext;
 use yari_sys::ContextBuilder;
+use yari_sys::ExternalVariable;
 use yari_sys::Module;
+use yari_sys::ModuleNode;
+use yari_sys::Profile;
+use yari_sys::Renderer;
+use yari_sys::Rule;
+use yari_sys::Sample;
+use yari_sys::StringMatch;
 use yari_sys::YrValue;
 
 // Exception type for yari python module.
@@ -24,17 +38,14 @@ struct Context {
 ///
 /// Return values are modeled similar to Python built-in `eval` function. This function converts
 /// values to the Python native types. For example `Vec<YrValue>` is converted to `list[YrValue]`.
-/// Same applies to structures and dictionaries.
+/// Same applies to structures and dictionaries, which keep the order of their members.
 fn yr_value_to_py_object(py: Python<'_>, yr_value: &YrValue) -> PyObject {
     match yr_value {
         YrValue::Integer(i) => i.into_py(py),
         YrValue::Float(f) => f.into_py(py),
         YrValue::String(s) => s.as_ref().into_py(py),
-        YrValue::Dictionary(d) => d
-            .iter()
-            .map(|(k, v)| (k, yr_value_to_py_object(py, v)))
-            .collect::<HashMap<_, _>>()
-            .into_py(py),
+        YrValue::Bytes(b) => PyBytes::new(py, b).into_py(py),
+        YrValue::Dictionary(d) => index_map_to_py_dict(py, d),
         YrValue::Array(a) => a
             .iter()
             .map(|val| yr_value_to_py_object(py, val))
@@ -42,13 +53,173 @@ fn yr_value_to_py_object(py: Python<'_>, yr_value: &YrValue) -> PyObject {
             .into_py(py),
         YrValue::Structure(s) => s
             .as_ref()
-            .map(|map| {
-                map.iter()
-                    .map(|(k, v)| (k, yr_value_to_py_object(py, v)))
-                    .collect::<HashMap<_, _>>()
-            })
-            .into_py(py),
+            .map_or_else(|| py.None(), |map| index_map_to_py_dict(py, map)),
+    }
+}
+
+/// Helper to convert the members to a `dict` preserving their order.
+fn index_map_to_py_dict(py: Python<'_>, map: &IndexMap<String, YrValue>) -> PyObject {
+    let dict = PyDict::new(py);
+    for (k, v) in map {
+        // Keys are unique strings, so the insertion can not fail
+        let _ = dict.set_item(k, yr_value_to_py_object(py, v));
     }
+    dict.into()
+}
+
+/// Helper to convert Python value to an `ExternalVariable`.
+///
+/// `bool` has to be checked before `int`, because it is a subclass of `int` in Python.
+fn py_object_to_external_variable(value: &PyAny) -> PyResult<ExternalVariable> {
+    if let Ok(b) = value.downcast::<PyBool>() {
+        Ok(ExternalVariable::Boolean(b.is_true()))
+    } else if let Ok(i) = value.downcast::<PyLong>() {
+        Ok(ExternalVariable::Integer(i.extract()?))
+    } else if let Ok(f) = value.downcast::<PyFloat>() {
+        Ok(ExternalVariable::Float(f.value()))
+    } else if let Ok(s) = value.downcast::<PyString>() {
+        Ok(ExternalVariable::String(s.to_str()?.to_string()))
+    } else {
+        Err(YariError::new_err(format!(
+            "unsupported type of external variable value '{}'",
+            value.get_type().name()?
+        )))
+    }
+}
+
+/// Helper to convert Python value to a `Sample`, `str` is a path and `bytes` are the content.
+fn py_object_to_sample(sample: &PyAny) -> PyResult<Sample> {
+    if let Ok(bytes) = sample.downcast::<PyBytes>() {
+        Ok(Sample::Bytes(bytes.as_bytes().to_vec()))
+    } else if let Ok(path) = sample.downcast::<PyString>() {
+        Ok(Sample::File(PathBuf::from(path.to_str()?)))
+    } else {
+        Err(YariError::new_err(
+            "`sample` is expected to be a path or bytes".to_string(),
+        ))
+    }
+}
+
+/// Helper to convert compiler `Diagnostic` to a python dictionary.
+fn diagnostic_to_py_object(py: Python<'_>, diagnostic: &Diagnostic) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("severity", diagnostic.severity.to_string())?;
+    dict.set_item("file", diagnostic.file.as_ref())?;
+    dict.set_item("line", diagnostic.line)?;
+    dict.set_item("rule", diagnostic.rule.as_ref())?;
+    dict.set_item("message", &diagnostic.message)?;
+    Ok(dict.into())
+}
+
+/// Helper to convert `Rule` to a python dictionary.
+fn rule_to_py_object(py: Python<'_>, rule: &Rule) -> PyResult<PyObject> {
+    let metas = PyDict::new(py);
+    for (name, value) in &rule.metas {
+        match value {
+            MetaValue::Integer(i) => metas.set_item(name, i)?,
+            MetaValue::String(s) => metas.set_item(name, s)?,
+            MetaValue::Boolean(b) => metas.set_item(name, b)?,
+        }
+    }
+
+    let strings = rule
+        .strings
+        .iter()
+        .map(|string| {
+            let dict = PyDict::new(py);
+            dict.set_item("identifier", &string.identifier)?;
+            dict.set_item("modifiers", &string.modifiers)?;
+            dict.set_item("matches", string.matches)?;
+            Ok(dict.into())
+        })
+        .collect::<PyResult<Vec<PyObject>>>()?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("name", &rule.name)?;
+    dict.set_item("namespace", &rule.namespace)?;
+    dict.set_item("is_private", rule.is_private)?;
+    dict.set_item("is_global", rule.is_global)?;
+    dict.set_item("tags", &rule.tags)?;
+    dict.set_item("metas", metas)?;
+    dict.set_item("strings", strings)?;
+    Ok(dict.into())
+}
+
+/// Helper to convert `StringMatch` to a python dictionary, matched data are `bytes`.
+fn string_match_to_py_object(py: Python<'_>, m: &StringMatch) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("offset", m.offset)?;
+    dict.set_item("base", m.base)?;
+    dict.set_item("length", m.length)?;
+    dict.set_item("data", PyBytes::new(py, &m.data))?;
+    dict.set_item("xor_key", m.xor_key)?;
+    dict.set_item("chain_length", m.chain_length)?;
+    Ok(dict.into())
+}
+
+/// Convert the module structure to nested dictionaries.
+///
+/// Every node has `name`, `kind` and `children` keys, scalar objects have `value` (`None` if
+/// undefined) and functions have `prototypes` and `return_type`.
+fn module_node_to_py_object(py: Python<'_>, node: &ModuleNode) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("name", &node.name)?;
+    dict.set_item("kind", node.kind.to_string())?;
+    match (&node.value, node.kind) {
+        (Some(value), _) => dict.set_item("value", yr_value_to_py_object(py, value))?,
+        (None, ObjectKind::Integer | ObjectKind::Float | ObjectKind::String) => {
+            dict.set_item("value", py.None())?
+        }
+        _ => {}
+    }
+    if let Some(reference) = &node.reference {
+        dict.set_item("reference", reference)?;
+    }
+    if node.kind == ObjectKind::Function {
+        dict.set_item("prototypes", &node.prototypes)?;
+        dict.set_item("return_type", node.return_kind.map(|k| k.to_string()))?;
+    }
+    let children = node
+        .children
+        .iter()
+        .map(|child| module_node_to_py_object(py, child))
+        .collect::<PyResult<Vec<PyObject>>>()?;
+    dict.set_item("children", children)?;
+    Ok(dict.into())
+}
+
+/// Convert the profile to a dictionary with `rules` and `strings` lists, times are in seconds.
+fn profile_to_py_object(py: Python<'_>, profile: &Profile) -> PyResult<PyObject> {
+    let rules = profile
+        .rules
+        .iter()
+        .map(|r| {
+            let dict = PyDict::new(py);
+            dict.set_item("rule", &r.rule)?;
+            dict.set_item("cost", r.cost)?;
+            dict.set_item("atom_matches", r.atom_matches)?;
+            dict.set_item("match_time", r.match_time.as_secs_f64())?;
+            Ok(dict.into())
+        })
+        .collect::<PyResult<Vec<PyObject>>>()?;
+
+    let strings = profile
+        .strings
+        .iter()
+        .map(|s| {
+            let dict = PyDict::new(py);
+            dict.set_item("rule", &s.rule)?;
+            dict.set_item("identifier", &s.identifier)?;
+            dict.set_item("atom_matches", s.atom_matches)?;
+            dict.set_item("match_time", s.match_time.as_secs_f64())?;
+            Ok(dict.into())
+        })
+        .collect::<PyResult<Vec<PyObject>>>()?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("rules", rules)?;
+    dict.set_item("strings", strings)?;
+    Ok(dict.into())
 }
 
 #[pymethods]
@@ -56,19 +227,29 @@ impl Context {
     /// Context constructor.
     ///
     /// Call without arguments will initialize default context matching /dev/null, without rule or
-    /// any module data. `sample` and `rule` are expected to be valid paths. `module_data` is a
-    /// python dictionary where key is a module name and data is a path to a file.
+    /// any module data. `sample` is either a valid path or `bytes` with the sample content.
+    /// `rule_path` is expected to be a valid path. `module_data` is a python dictionary where key
+    /// is a module name and data is a path to a file. `externals` is a python dictionary of
+    /// external variables, values can be `bool`, `int`, `float` or `str`. `compiled_rule_path` is
+    /// a path to rules compiled by `yarac`, it cannot be combined with the other rule sources.
+    /// `profiling` enables profiling of the scan, see `profile`.
     #[new]
     fn new(
-        sample: Option<&PyString>,
+        sample: Option<&PyAny>,
         rule_string: Option<&PyString>,
         rule_path: Option<&PyString>,
         module_data: Option<&PyDict>,
+        externals: Option<&PyDict>,
+        compiled_rule_path: Option<&PyString>,
+        profiling: Option<bool>,
     ) -> PyResult<Self> {
         let mut builder = ContextBuilder::default();
 
         if let Some(sample) = sample {
-            builder = builder.with_sample(Some(sample.to_string()));
+            builder = match py_object_to_sample(sample)? {
+                Sample::File(path) => builder.with_sample(Some(path)),
+                Sample::Bytes(bytes) => builder.with_sample_bytes(bytes),
+            };
         }
 
         // Use either `rule_string` or `rule_path`. Invalid configuration of arguments raises
@@ -89,6 +270,14 @@ impl Context {
             (_, _) => {},
         }
 
+        if let Some(compiled_rule_path) = compiled_rule_path {
+            builder = builder.with_compiled_rules(compiled_rule_path.to_str()?);
+        }
+
+        if let Some(profiling) = profiling {
+            builder = builder.with_profiling(profiling);
+        }
+
         // Add the module data
         if let Some(module_data) = module_data {
             for (module, data) in module_data {
@@ -103,6 +292,14 @@ impl Context {
             }
         }
 
+        // Define the external variables
+        if let Some(externals) = externals {
+            for (name, value) in externals {
+                let name = name.downcast::<PyString>()?.to_str()?;
+                builder = builder.with_external(name, py_object_to_external_variable(value)?);
+            }
+        }
+
         Ok(Context {
             inner: builder
                 .build()
@@ -110,6 +307,97 @@ impl Context {
         })
     }
 
+    /// Warnings reported by the compiler.
+    ///
+    /// Each warning is a dictionary with `severity`, `file`, `line`, `rule` and `message` keys.
+    #[getter]
+    pub fn warnings(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        self.inner
+            .diagnostics()
+            .iter()
+            .map(|d| diagnostic_to_py_object(py, d))
+            .collect()
+    }
+
+    /// Rules compiled into the context.
+    ///
+    /// Each rule is a dictionary with `name`, `namespace`, `is_private`, `is_global`, `tags`,
+    /// `metas` and `strings` keys. Strings are dictionaries with `identifier`, `modifiers` and
+    /// `matches` (number of matches in the sample) keys.
+    #[getter]
+    pub fn rules(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        self.inner
+            .rules()
+            .iter()
+            .map(|r| rule_to_py_object(py, r))
+            .collect()
+    }
+
+    /// Modules imported by the rules.
+    #[getter]
+    pub fn imports(&self) -> Vec<String> {
+        self.inner.imports()
+    }
+
+    /// Replace the sample of the context, `sample` is either a valid path or `bytes`.
+    ///
+    /// Compiled rules and module data are kept.
+    pub fn set_sample(&mut self, sample: &PyAny) -> PyResult<()> {
+        self.inner
+            .set_sample(py_object_to_sample(sample)?)
+            .map_err(|e| YariError::new_err(e.to_string()))
+    }
+
+    /// All the matches of the string `identifier` declared in the `rule`.
+    ///
+    /// Each match is a dictionary with `offset`, `base`, `length`, `data`, `xor_key` and
+    /// `chain_length` keys. `xor_key` is `None` for strings without the `xor` modifier.
+    pub fn string_matches(
+        &mut self,
+        py: Python<'_>,
+        rule: &str,
+        identifier: &str,
+    ) -> PyResult<Vec<PyObject>> {
+        self.inner
+            .string_matches(rule, identifier)
+            .map_err(|e| YariError::new_err(e.to_string()))?
+            .iter()
+            .map(|m| string_match_to_py_object(py, m))
+            .collect()
+    }
+
+    /// Cost of the rules and strings in the profiled scan, the most expensive go first.
+    ///
+    /// Returns a dictionary with `rules` and `strings` lists. Rule `cost` is reported by libyara
+    /// and is `None` unless built with the `profiling` feature. Raises `YariError` if the
+    /// context was not created with `profiling=True`.
+    pub fn profile(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let profile = self
+            .inner
+            .profile()
+            .ok_or_else(|| YariError::new_err("context was created without profiling"))?;
+        profile_to_py_object(py, profile)
+    }
+
+    /// Explain the result of the `rule` as an annotated tree of its condition.
+    pub fn explain(&mut self, rule: &str) -> PyResult<String> {
+        self.inner
+            .explain(rule)
+            .map(|explanation| explanation.to_string())
+            .map_err(|e| YariError::new_err(e.to_string()))
+    }
+
+    /// Structure of the module `name` with the values for the current sample as nested
+    /// dictionaries.
+    pub fn dump_module(&mut self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let module = Module::from_str(name).map_err(|e| YariError::new_err(e.to_string()))?;
+        let node = self
+            .inner
+            .dump_module(module)
+            .map_err(|e| YariError::new_err(e.to_string()))?;
+        module_node_to_py_object(py, &node)
+    }
+
     /// Evaluate YARA expression.
     ///
     /// This function behaves like python build-in `eval` function and returns dynamic type based
@@ -122,6 +410,18 @@ impl Context {
             .map_err(|e| YariError::new_err(e.to_string()))
     }
 
+    /// Evaluate YARA expression and render the result as an indented tree.
+    ///
+    /// Integers are shown in decimal and hexadecimal and only the first items of long arrays
+    /// are rendered unless `full` is set.
+    pub fn render(&mut self, expr: &str, full: Option<bool>) -> PyResult<String> {
+        let renderer = Renderer::default().with_full(full.unwrap_or(false));
+        self.inner
+            .eval(expr)
+            .map(|val| renderer.render(&val))
+            .map_err(|e| YariError::new_err(e.to_string()))
+    }
+
     /// Evaluate YARA expression returning `bool` using YARA conversion rules.
     pub fn eval_bool(&mut self, expr: &str) -> PyResult<bool> {
         self.inner
@@ -25,3 +25,193 @@ def test_context_with_rule_path(tmp_path):
     )
     c = yari.Context(rule_path=str(rule_path))
     c.eval("time.now()")
+
+
+def test_context_with_externals():
+    c = yari.Context(
+        rule_string="""rule test {
+    condition:
+        int_var == 10 and bool_var and str_var == "test"
+}""",
+        externals={"int_var": 10, "float_var": 1.5, "bool_var": True, "str_var": "test"},
+    )
+    assert c.eval("int_var") == 10
+    assert c.eval("float_var") == 1.5
+    assert c.eval("bool_var") == 1
+    assert c.eval("str_var") == "test"
+    assert c.eval_bool("test")
+
+
+def test_context_with_invalid_externals():
+    with pytest.raises(yari.YariError):
+        _ = yari.Context(externals={"var": [1, 2]})
+
+
+def test_context_with_nul_in_external_string():
+    with pytest.raises(yari.YariError):
+        _ = yari.Context(externals={"str_var": "a\0b"})
+
+
+def test_context_with_sample_bytes(assets):
+    sample = (assets / "pe_hello_world").read_binary()
+    c = yari.Context(sample=sample)
+    assert c.eval("pe.number_of_sections") == 4
+    assert c.eval("filesize") == len(sample)
+
+
+def test_context_with_invalid_sample():
+    with pytest.raises(yari.YariError):
+        _ = yari.Context(sample=1)
+
+
+def test_context_compile_error():
+    with pytest.raises(yari.YariError) as e:
+        _ = yari.Context(rule_string="rule test { condition: undefined_identifier }")
+    assert "undefined_identifier" in str(e.value)
+
+
+def test_context_warnings():
+    c = yari.Context(
+        rule_string="""rule test {
+    strings:
+        $s00 = { 00 }
+    condition:
+        $s00
+}"""
+    )
+    assert len(c.warnings) > 0
+    assert c.warnings[0]["severity"] == "warning"
+    assert c.warnings[0]["rule"] == "test"
+    assert yari.Context().warnings == []
+
+
+def test_context_set_sample(assets):
+    c = yari.Context(sample=str(assets / "elf_hello_world"))
+    assert c.eval("elf.number_of_sections") == 30
+
+    c.set_sample(str(assets / "pe_hello_world"))
+    assert c.eval("pe.number_of_sections") == 4
+
+    c.set_sample(b"data")
+    assert c.eval("filesize") == 4
+
+
+def test_context_with_invalid_compiled_rules(tmp_path):
+    rule_path = tmp_path / "test.yar"
+    rule_path.write_text("rule test { condition: true }")
+
+    with pytest.raises(yari.YariError) as e:
+        _ = yari.Context(compiled_rule_path=str(rule_path))
+    assert "not a compiled rules file" in str(e.value)
+
+    with pytest.raises(yari.YariError):
+        _ = yari.Context(rule_path=str(rule_path), compiled_rule_path=str(rule_path))
+
+
+def test_context_rules():
+    c = yari.Context(
+        sample=b"Hello world",
+        rule_string="""import "pe"
+
+private rule r : tag {
+    meta:
+        author = "test"
+        version = 2
+    strings:
+        $s00 = "Hello" nocase
+    condition:
+        $s00
+}""",
+    )
+    assert c.imports == ["pe"]
+    assert c.rules == [
+        {
+            "name": "r",
+            "namespace": "default",
+            "is_private": True,
+            "is_global": False,
+            "tags": ["tag"],
+            "metas": {"author": "test", "version": 2},
+            "strings": [{"identifier": "$s00", "modifiers": ["nocase"], "matches": 1}],
+        }
+    ]
+    assert yari.Context().rules == []
+
+
+def test_context_string_matches():
+    c = yari.Context(
+        sample=b"Hello world",
+        rule_string="""rule r {
+    strings:
+        $s00 = "o"
+    condition:
+        $s00
+}""",
+    )
+    assert c.string_matches("r", "$s00") == [
+        {"offset": 4, "base": 0, "length": 1, "data": b"o", "xor_key": None, "chain_length": 0},
+        {"offset": 7, "base": 0, "length": 1, "data": b"o", "xor_key": None, "chain_length": 0},
+    ]
+    assert [m["offset"] for m in c.eval("r|matches($s00)")] == [4, 7]
+
+    with pytest.raises(yari.YariError):
+        c.string_matches("r", "$s01")
+
+
+def test_context_explain():
+    c = yari.Context(
+        sample=b"Hello world",
+        rule_string="""rule r {
+    condition:
+        filesize == 0 and filesize > 5
+}""",
+    )
+    assert c.explain("r") == (
+        "filesize == 0 and filesize > 5 => 0\n"
+        "├── filesize == 0 => 0\n"
+        "│   ├── filesize => 11\n"
+        "│   └── 0 => 0\n"
+        "└── filesize > 5 => short-circuited\n"
+    )
+
+    with pytest.raises(yari.YariError):
+        c.explain("missing")
+
+
+def test_context_profile():
+    c = yari.Context(
+        sample=b"Hello world",
+        rule_string="""rule r {
+    strings:
+        $s00 = "o"
+        $s01 = "Hello"
+    condition:
+        any of them
+}""",
+        profiling=True,
+    )
+    profile = c.profile()
+    assert [r["rule"] for r in profile["rules"]] == ["default:r"]
+    assert profile["rules"][0]["atom_matches"] >= 3
+    assert sorted(s["identifier"] for s in profile["strings"]) == ["$s00", "$s01"]
+    assert all(s["match_time"] >= 0 for s in profile["strings"])
+
+    with pytest.raises(yari.YariError):
+        yari.Context().profile()
+
+
+def test_context_dump_module(context_with_pe_and_rule):
+    tree = context_with_pe_and_rule.dump_module("pe")
+    assert tree["name"] == "pe"
+    assert tree["kind"] == "structure"
+
+    children = {c["name"]: c for c in tree["children"]}
+    assert children["number_of_sections"]["value"] == 4
+    assert children["exports"]["kind"] == "function"
+    assert "s" in children["exports"]["prototypes"]
+    assert children["exports"]["return_type"] == "integer"
+
+
+def test_context_dump_unknown_module_raises(context):
+    with pytest.raises(yari.YariError):
+        context.dump_module("unknown")
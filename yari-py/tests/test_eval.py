@@ -6,6 +6,8 @@ import yari
     "expr, res",
     [
         ('hash.md5("dummy")', "275876e34cf609db118f3d84b799a790"),
+        ("math.in_range(7.5, 7.0, 8.0)", 1),
+        ('math.deviation("data", 64.1)', pytest.approx(38.4)),
     ],
 )
 def test_eval(expr, res, context, context_with_pe_and_rule, context_with_elf):
@@ -40,6 +42,8 @@ def test_eval_bool(expr, context):
         ("r|#s00", 1),
         ("r|@s00[1]", 1212),
         ("r|!s00[1]", 5),
+        ("hash.md5(0, filesize) == hash.sha256(0, filesize)", 0),
+        ("hash.md5(0, filesize) == hash.md5(0, 8704)", 1),
     ],
 )
 def test_eval_pe_and_rule(expr, res, context_with_pe_and_rule):
@@ -77,3 +81,48 @@ def test_eval_with_missing_string_raises_exception(context_with_pe_and_rule):
 def test_eval_with_missing_rule_raises_excpetion(context_with_pe_and_rule):
     with pytest.raises(yari.YariError) as e:
         context_with_pe_and_rule.eval("this_rule_should_be_missing|$s123")
+
+
+@pytest.mark.parametrize(
+    "expr, res",
+    [
+        ("r|#s*", 1),
+        ("r|$s*", {"$s00": 1, "$s01": 0}),
+        ("r|any of ($s*)", True),
+        ("r|all of them", False),
+        ("r|1 of them", True),
+        ("r|#s00 in (0..filesize)", 1),
+        ("r|$s00 at 0", False),
+    ],
+)
+def test_eval_string_sets(expr, res, context_with_pe_and_rule):
+    assert context_with_pe_and_rule.eval(expr) == res
+
+
+@pytest.mark.parametrize(
+    "expr, res",
+    [
+        (r'"a\\b\n"', "a\\b\n"),
+        (r'"\xff\x00"', b"\xff\x00"),
+        (r'"\xc3\xa9"', "é"),
+    ],
+)
+def test_eval_string_values_are_raw(expr, res, context):
+    assert context.eval(expr) == res
+
+
+def test_eval_structure_keeps_declaration_order(context_with_pe_and_rule):
+    section = context_with_pe_and_rule.eval("pe.sections[0]")
+    assert list(section)[:3] == ["name", "full_name", "characteristics"]
+
+
+def test_render(context_with_pe_and_rule):
+    assert context_with_pe_and_rule.render("pe.number_of_sections") == "4 (0x4)"
+
+    rendered = context_with_pe_and_rule.render("pe.sections")
+    assert rendered.startswith("array[4]\n  [0]: structure\n")
+
+
+def test_render_raises(context):
+    with pytest.raises(yari.YariError):
+        context.render("unknown")
@@ -0,0 +1,97 @@
+use crate::bindings::YR_AC_MATCH;
+use crate::bindings::YR_ATOM_QUALITY_WARNING_THRESHOLD;
+use crate::bindings::YR_RULES;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Atom chosen by the compiler for a string.
+///
+/// Every occurrence of the atom in the sample is a candidate match which has to be verified, so
+/// atoms with low quality which hit often slow the scan down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Atom {
+    pub bytes: Vec<u8>,
+    /// Offset of the atom in the string
+    pub backtrack: u16,
+    /// Quality of the atom as computed by the compiler, higher is better
+    pub quality: i32,
+    /// Number of the atom hits in the sample
+    pub hits: u64,
+}
+
+impl Atom {
+    /// Check if the atom quality is so low that the compiler warns about the string.
+    pub fn is_slow(&self) -> bool {
+        self.quality < YR_ATOM_QUALITY_WARNING_THRESHOLD as i32
+    }
+}
+
+impl fmt::Display for Atom {
+    /// ```
+    /// # use yari_sys::atoms::Atom;
+    /// let atom = Atom { bytes: b"Hel\x00".to_vec(), backtrack: 0, quality: 80, hits: 1 };
+    /// assert_eq!(atom.to_string(), "48 65 6c 00 \"Hel\\x00\"");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self
+            .bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let escaped: String = self
+            .bytes
+            .iter()
+            .flat_map(|b| std::ascii::escape_default(*b))
+            .map(char::from)
+            .collect();
+        write!(f, "{} \"{}\"", hex, escaped)
+    }
+}
+
+/// Atoms of the string declared in a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringAtoms {
+    pub identifier: String,
+    pub atoms: Vec<Atom>,
+}
+
+/// Bytes of the atom of every match in the Aho-Corasick automaton of the `rules`.
+///
+/// Atoms are not stored in the compiled rules, they are reconstructed from the paths of the
+/// automaton states. Match list of a state continues with the matches of its failure state, so
+/// the match belongs to the shallowest state listing it.
+pub(crate) unsafe fn automaton_atoms(rules: &YR_RULES) -> HashMap<*const YR_AC_MATCH, Vec<u8>> {
+    let transition_table = rules.ac_transition_table;
+    let match_table = rules.ac_match_table;
+
+    let mut atoms = HashMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([(0_u32, Vec::new())]); // YR_AC_ROOT_STATE
+
+    while let Some((state, path)) = queue.pop_front() {
+        if !visited.insert(state) {
+            continue;
+        }
+
+        let match_index = *match_table.add(state as usize);
+        if match_index != 0 {
+            let mut m: *const YR_AC_MATCH = rules.ac_match_pool.add(match_index as usize - 1);
+            while !m.is_null() {
+                atoms.entry(m).or_insert_with(|| path.clone());
+                m = (*m).__bindgen_anon_4.next;
+            }
+        }
+
+        for byte in 0..=255_u8 {
+            let index = byte as u32 + 1;
+            let transition = *transition_table.add(state as usize + index as usize);
+            if transition & 0x1FF == index && transition >> 9 != 0 {
+                let mut child_path = path.clone();
+                child_path.push(byte);
+                queue.push_back((transition >> 9, child_path));
+            }
+        }
+    }
+    atoms
+}
@@ -0,0 +1,400 @@
+use crate::bindings::SIZED_STRING;
+use crate::bindings::YR_RULE;
+use crate::rules::{qualified_name, string_identifiers};
+use crate::YariError;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::c_char;
+
+const OP_INIT_RULE: u8 = 28;
+const OP_MATCH_RULE: u8 = 29;
+const OP_MATCHES: u8 = 40;
+const OP_HALT: u8 = 255;
+
+/// Encoding of the operand following the opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    None,
+    /// Unsigned 8, 16 or 32 bit integer, or 64 bit integer which can be a pointer
+    Integer(usize),
+    /// 64 bit index of the loop memory slot
+    Memory,
+    /// 32 bit jump offset relative to the opcode
+    Jump,
+    /// 64 bit index to the rules table
+    Rule,
+    /// 32 bit jump offset followed by 32 bit index to the rules table
+    InitRule,
+    /// Pointer to a null-terminated string
+    Identifier,
+}
+
+/// Mnemonic and operand encoding of the `opcode` as defined by libyara (`exec.h`).
+fn opcode_info(opcode: u8) -> Option<(&'static str, Encoding)> {
+    let info = match opcode {
+        0 => ("error", Encoding::None),
+        1 => ("and", Encoding::None),
+        2 => ("or", Encoding::None),
+        3 => ("not", Encoding::None),
+        4 => ("bitwise_not", Encoding::None),
+        5 => ("bitwise_and", Encoding::None),
+        6 => ("bitwise_or", Encoding::None),
+        7 => ("bitwise_xor", Encoding::None),
+        8 => ("shl", Encoding::None),
+        9 => ("shr", Encoding::None),
+        10 => ("mod", Encoding::None),
+        11 => ("int_to_dbl", Encoding::Integer(8)),
+        12 => ("str_to_bool", Encoding::None),
+        13 => ("push", Encoding::Integer(8)),
+        14 => ("pop", Encoding::None),
+        15 => ("call", Encoding::Identifier),
+        16 => ("obj_load", Encoding::Identifier),
+        17 => ("obj_value", Encoding::None),
+        18 => ("obj_field", Encoding::Identifier),
+        19 => ("index_array", Encoding::None),
+        20 => ("count", Encoding::None),
+        21 => ("length", Encoding::None),
+        22 => ("found", Encoding::None),
+        23 => ("found_at", Encoding::None),
+        24 => ("found_in", Encoding::None),
+        25 => ("offset", Encoding::None),
+        26 => ("of", Encoding::None),
+        27 => ("push_rule", Encoding::Rule),
+        OP_INIT_RULE => ("init_rule", Encoding::InitRule),
+        OP_MATCH_RULE => ("match_rule", Encoding::Rule),
+        30 => ("incr_m", Encoding::Memory),
+        31 => ("clear_m", Encoding::Memory),
+        32 => ("add_m", Encoding::Memory),
+        33 => ("pop_m", Encoding::Memory),
+        34 => ("push_m", Encoding::Memory),
+        35 => ("set_m", Encoding::Memory),
+        36 => ("swapundef", Encoding::Memory),
+        37 => ("filesize", Encoding::None),
+        38 => ("entrypoint", Encoding::None),
+        OP_MATCHES => ("matches", Encoding::None),
+        41 => ("import", Encoding::Identifier),
+        42 => ("lookup_dict", Encoding::None),
+        43 => ("jundef", Encoding::Jump),
+        44 => ("jundef_p", Encoding::Jump),
+        45 => ("jnundef", Encoding::Jump),
+        46 => ("jnundef_p", Encoding::Jump),
+        47 => ("jfalse", Encoding::Jump),
+        48 => ("jfalse_p", Encoding::Jump),
+        49 => ("jtrue", Encoding::Jump),
+        50 => ("jtrue_p", Encoding::Jump),
+        51 => ("jl_p", Encoding::Jump),
+        52 => ("jle_p", Encoding::Jump),
+        53 => ("iter_next", Encoding::None),
+        54 => ("iter_start_array", Encoding::None),
+        55 => ("iter_start_dict", Encoding::None),
+        56 => ("iter_start_int_range", Encoding::None),
+        57 => ("iter_start_int_enum", Encoding::None),
+        58 => ("jz", Encoding::Jump),
+        59 => ("jz_p", Encoding::Jump),
+        60 => ("push_8", Encoding::Integer(1)),
+        61 => ("push_16", Encoding::Integer(2)),
+        62 => ("push_32", Encoding::Integer(4)),
+        63 => ("push_u", Encoding::None),
+        64 => ("contains", Encoding::None),
+        65 => ("startswith", Encoding::None),
+        66 => ("endswith", Encoding::None),
+        67 => ("icontains", Encoding::None),
+        68 => ("istartswith", Encoding::None),
+        69 => ("iendswith", Encoding::None),
+        70 => ("iequals", Encoding::None),
+        71 => ("of_percent", Encoding::None),
+        72 => ("of_found_in", Encoding::None),
+        73 => ("count_in", Encoding::None),
+        74 => ("defined", Encoding::None),
+        100 => ("int_eq", Encoding::None),
+        101 => ("int_neq", Encoding::None),
+        102 => ("int_lt", Encoding::None),
+        103 => ("int_gt", Encoding::None),
+        104 => ("int_le", Encoding::None),
+        105 => ("int_ge", Encoding::None),
+        106 => ("int_add", Encoding::None),
+        107 => ("int_sub", Encoding::None),
+        108 => ("int_mul", Encoding::None),
+        109 => ("int_div", Encoding::None),
+        110 => ("int_minus", Encoding::None),
+        120 => ("dbl_eq", Encoding::None),
+        121 => ("dbl_neq", Encoding::None),
+        122 => ("dbl_lt", Encoding::None),
+        123 => ("dbl_gt", Encoding::None),
+        124 => ("dbl_le", Encoding::None),
+        125 => ("dbl_ge", Encoding::None),
+        126 => ("dbl_add", Encoding::None),
+        127 => ("dbl_sub", Encoding::None),
+        128 => ("dbl_mul", Encoding::None),
+        129 => ("dbl_div", Encoding::None),
+        130 => ("dbl_minus", Encoding::None),
+        140 => ("str_eq", Encoding::None),
+        141 => ("str_neq", Encoding::None),
+        142 => ("str_lt", Encoding::None),
+        143 => ("str_gt", Encoding::None),
+        144 => ("str_le", Encoding::None),
+        145 => ("str_ge", Encoding::None),
+        254 => ("nop", Encoding::None),
+        OP_HALT => ("halt", Encoding::None),
+        _ => return None,
+    };
+    Some(info)
+}
+
+/// Check if the `opcode` consumes a string literal on the top of the stack.
+fn takes_string_literal(opcode: u8) -> bool {
+    matches!(opcode, 12 | 64..=70 | 140..=145)
+}
+
+/// Operand of the disassembled instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Integer(i64),
+    /// Loop memory slot
+    Memory(u64),
+    /// Jump target as an offset from the start of the code, labeled relative to the rule
+    Jump {
+        target: usize,
+        label: String,
+    },
+    Rule(String),
+    /// String declared in a rule (e.g. `$s00`)
+    String(String),
+    /// Text string literal, escaped
+    Literal(String),
+    /// Object identifier, module name or function arguments format
+    Identifier(String),
+    /// Compiled regular expression
+    Regexp(u64),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Integer(i) => write!(f, "{}", i),
+            Operand::Memory(slot) => write!(f, "m[{}]", slot),
+            Operand::Jump { target, label } if label.is_empty() => write!(f, "{:#06x}", target),
+            Operand::Jump { target, label } => write!(f, "{:#06x} <{}>", target, label),
+            Operand::Rule(name) => write!(f, "{}", name),
+            Operand::String(identifier) => write!(f, "{}", identifier),
+            Operand::Literal(literal) => write!(f, "\"{}\"", literal),
+            Operand::Identifier(identifier) => write!(f, "{}", identifier),
+            Operand::Regexp(address) => write!(f, "regexp@{:#x}", address),
+        }
+    }
+}
+
+/// Instruction of the rule condition bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Offset of the instruction from the start of the code
+    pub offset: usize,
+    pub opcode: u8,
+    pub mnemonic: &'static str,
+    pub operands: Vec<Operand>,
+}
+
+impl fmt::Display for Instruction {
+    /// ```
+    /// # use yari_sys::disasm::{Instruction, Operand};
+    /// let instruction = Instruction {
+    ///     offset: 0x1a,
+    ///     opcode: 48,
+    ///     mnemonic: "jfalse_p",
+    ///     operands: vec![Operand::Jump { target: 0x2b, label: "r+0x2b".to_string() }],
+    /// };
+    /// assert_eq!(instruction.to_string(), "0x001a  jfalse_p         0x002b <r+0x2b>");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operands = self
+            .operands
+            .iter()
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let line = format!("{:#06x}  {:<16} {}", self.offset, self.mnemonic, operands);
+        write!(f, "{}", line.trim_end())
+    }
+}
+
+/// Names of the rules and strings referenced by the bytecode.
+#[derive(Debug, Default)]
+pub(crate) struct Symbols {
+    /// Qualified rule names in the order of the rules table
+    rules: Vec<String>,
+    /// String identifiers by the address of the string
+    strings: HashMap<usize, String>,
+}
+
+impl Symbols {
+    /// Add the next `rule` of the rules table.
+    ///
+    /// Strings can be referenced only by the rule declaring them, so they are named by the
+    /// identifier only.
+    pub(crate) unsafe fn add_rule(&mut self, rule: &YR_RULE) {
+        for (string, identifier) in string_identifiers(rule) {
+            self.strings.insert(string as usize, identifier);
+        }
+        self.rules.push(qualified_name(rule));
+    }
+
+    fn rule(&self, index: u64) -> Operand {
+        match self.rules.get(index as usize) {
+            Some(name) => Operand::Rule(name.clone()),
+            None => Operand::Integer(index as i64),
+        }
+    }
+}
+
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+unsafe fn read_le(code: *const u8, size: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    std::ptr::copy_nonoverlapping(code, bytes.as_mut_ptr(), size);
+    u64::from_le_bytes(bytes)
+}
+
+/// Decode the bytecode starting at `code_start` up to the halt instruction.
+pub(crate) unsafe fn disassemble(
+    code_start: *const u8,
+    symbols: &Symbols,
+) -> Result<Vec<Instruction>, YariError> {
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let opcode = *code_start.add(offset);
+        let (mnemonic, encoding) =
+            opcode_info(opcode).ok_or(YariError::DisassemblerError(opcode, offset))?;
+        let operand = code_start.add(offset + 1);
+
+        let jump = |relative: i32| Operand::Jump {
+            target: (offset as i64 + relative as i64) as usize,
+            label: String::new(),
+        };
+
+        let (operands, size) = match encoding {
+            Encoding::None => (Vec::new(), 0),
+            Encoding::Integer(size) => {
+                (vec![Operand::Integer(read_le(operand, size) as i64)], size)
+            }
+            Encoding::Memory => (vec![Operand::Memory(read_le(operand, 8))], 8),
+            Encoding::Jump => (vec![jump(read_le(operand, 4) as i32)], 4),
+            Encoding::Rule => (vec![symbols.rule(read_le(operand, 8))], 8),
+            Encoding::InitRule => {
+                let target = jump(read_le(operand, 4) as i32);
+                (vec![symbols.rule(read_le(operand.add(4), 4)), target], 8)
+            }
+            Encoding::Identifier => {
+                let ptr = read_le(operand, 8) as *const c_char;
+                (vec![Operand::Identifier(c_string(ptr))], 8)
+            }
+        };
+
+        // Pushed values referring to strings are resolved once the consumer is known
+        if let Some(previous) = instructions.last_mut() {
+            resolve_push(previous, opcode, symbols);
+        }
+
+        instructions.push(Instruction {
+            offset,
+            opcode,
+            mnemonic,
+            operands,
+        });
+
+        if opcode == OP_HALT {
+            break;
+        }
+        offset += 1 + size;
+    }
+
+    label_jumps(&mut instructions);
+    Ok(instructions)
+}
+
+/// Resolve the value pushed by the `push` instruction followed by the `next_opcode`.
+///
+/// The values can be pointers to the strings declared in rules, to the text string literals or
+/// to the compiled regular expressions.
+unsafe fn resolve_push(push: &mut Instruction, next_opcode: u8, symbols: &Symbols) {
+    if push.mnemonic != "push" {
+        return;
+    }
+    let Some(Operand::Integer(value)) = push.operands.first() else {
+        return;
+    };
+    let address = *value as u64;
+
+    if let Some(identifier) = symbols.strings.get(&(address as usize)) {
+        push.operands[0] = Operand::String(identifier.clone());
+    } else if address == 0 {
+        // Null pointer is a valid integer
+    } else if takes_string_literal(next_opcode) {
+        let string = address as *const SIZED_STRING;
+        let bytes = std::slice::from_raw_parts(
+            (*string).c_string.as_ptr() as *const u8,
+            (*string).length as usize,
+        );
+        let literal = bytes
+            .iter()
+            .flat_map(|b| std::ascii::escape_default(*b))
+            .map(char::from)
+            .collect();
+        push.operands[0] = Operand::Literal(literal);
+    } else if next_opcode == OP_MATCHES {
+        push.operands[0] = Operand::Regexp(address);
+    }
+}
+
+/// Label jump targets by the rule they belong to and the offset from the start of the rule.
+fn label_jumps(instructions: &mut [Instruction]) {
+    let rule_starts: Vec<(usize, String)> = instructions
+        .iter()
+        .filter(|i| i.opcode == OP_INIT_RULE)
+        .filter_map(|i| match i.operands.first() {
+            Some(Operand::Rule(name)) => Some((i.offset, name.clone())),
+            _ => None,
+        })
+        .collect();
+
+    for instruction in instructions.iter_mut() {
+        for operand in instruction.operands.iter_mut() {
+            if let Operand::Jump { target, label } = operand {
+                if let Some((start, name)) =
+                    rule_starts.iter().rev().find(|(start, _)| start <= target)
+                {
+                    *label = format!("{}+{:#x}", name, *target - start);
+                }
+            }
+        }
+    }
+}
+
+/// Instructions of the rule `name` from its initialization to the rule match.
+pub(crate) fn rule_instructions(
+    instructions: Vec<Instruction>,
+    name: &str,
+) -> Result<Vec<Instruction>, YariError> {
+    let is_rule = |i: &Instruction, opcode| {
+        i.opcode == opcode && i.operands.first() == Some(&Operand::Rule(name.to_string()))
+    };
+
+    let start = instructions
+        .iter()
+        .position(|i| is_rule(i, OP_INIT_RULE))
+        .ok_or(YariError::RuleMissingError)?;
+    let end = instructions[start..]
+        .iter()
+        .position(|i| is_rule(i, OP_MATCH_RULE))
+        .map_or(instructions.len(), |end| start + end + 1);
+
+    Ok(instructions[start..end].to_vec())
+}
@@ -0,0 +1,178 @@
+use crate::YrValue;
+use serde::Serialize;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Kind of the object in the module structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectKind {
+    Integer,
+    Float,
+    String,
+    Structure,
+    Array,
+    Dictionary,
+    Function,
+    Reference,
+}
+
+impl ObjectKind {
+    /// Kind of the YARA object type (`OBJECT_TYPE_*`).
+    pub(crate) fn from_object_type(object_type: i8) -> Option<ObjectKind> {
+        let kind = match object_type {
+            1 => ObjectKind::Integer,
+            2 => ObjectKind::String,
+            3 => ObjectKind::Structure,
+            4 => ObjectKind::Array,
+            5 => ObjectKind::Function,
+            6 => ObjectKind::Dictionary,
+            7 => ObjectKind::Float,
+            8 => ObjectKind::Reference,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ObjectKind::Integer => "integer",
+            ObjectKind::Float => "float",
+            ObjectKind::String => "string",
+            ObjectKind::Structure => "structure",
+            ObjectKind::Array => "array",
+            ObjectKind::Dictionary => "dictionary",
+            ObjectKind::Function => "function",
+            ObjectKind::Reference => "reference",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Output format of the module dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DumpFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+impl FromStr for DumpFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(DumpFormat::Text),
+            "json" => Ok(DumpFormat::Json),
+            "yaml" => Ok(DumpFormat::Yaml),
+            _ => Err(format!("unknown dump format '{}'", s)),
+        }
+    }
+}
+
+/// Object of the module structure with its members.
+///
+/// Serialized with all the fields, undefined values and missing fields are `null`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModuleNode {
+    /// Identifier of the object, `[i]` for array items and the key for dictionary items
+    pub name: String,
+    pub kind: ObjectKind,
+    /// Value of integer, float and string objects, `None` if the value is undefined
+    pub value: Option<YrValue>,
+    /// Argument formats of the function overloads (e.g. `"si"`)
+    pub prototypes: Vec<String>,
+    /// Kind of the value returned by the function
+    #[serde(rename = "return_type")]
+    pub return_kind: Option<ObjectKind>,
+    /// Name of the referenced object
+    pub reference: Option<String>,
+    pub children: Vec<ModuleNode>,
+}
+
+impl ModuleNode {
+    pub(crate) fn new(name: String, kind: ObjectKind) -> ModuleNode {
+        ModuleNode {
+            name,
+            kind,
+            value: None,
+            prototypes: Vec::new(),
+            return_kind: None,
+            reference: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Format the tree in the `format`.
+    pub fn format(&self, format: DumpFormat) -> String {
+        match format {
+            DumpFormat::Text => {
+                let mut res = String::new();
+                self.write_text(&mut res, 0);
+                res
+            }
+            DumpFormat::Json => {
+                serde_json::to_string_pretty(self).expect("module tree is serializable") + "\n"
+            }
+            DumpFormat::Yaml => serde_yaml::to_string(self).expect("module tree is serializable"),
+        }
+    }
+
+    /// Text with one object per line, nesting is indented by tabs.
+    fn write_text(&self, out: &mut String, depth: usize) {
+        let indent = "\t".repeat(depth);
+        let _ = match self.kind {
+            ObjectKind::Integer => match &self.value {
+                Some(YrValue::Integer(i)) => writeln!(out, "{}{:?} = {:#x}", indent, self.name, i),
+                _ => writeln!(out, "{}{:?} = YR_UNDEFINED", indent, self.name),
+            },
+            ObjectKind::Float => match &self.value {
+                Some(YrValue::Float(v)) => writeln!(out, "{}{:?} = {}", indent, self.name, v),
+                _ => writeln!(out, "{}{:?} = YR_UNDEFINED", indent, self.name),
+            },
+            ObjectKind::String => match &self.value {
+                Some(value @ (YrValue::String(Some(_)) | YrValue::Bytes(_))) => writeln!(
+                    out,
+                    "{}[STR] {:?} = {}",
+                    indent,
+                    self.name,
+                    YrValue::escape_bytes(value.as_bytes().unwrap_or_default())
+                ),
+                _ => writeln!(out, "{}[STR] {:?} = NULL", indent, self.name),
+            },
+            ObjectKind::Function => {
+                let ret = self
+                    .return_kind
+                    .map_or("undefined".to_string(), |k| k.to_string());
+                for prototype in &self.prototypes {
+                    let _ = writeln!(out, "{}{:?}({:?}) -> {}", indent, self.name, prototype, ret);
+                }
+                Ok(())
+            }
+            ObjectKind::Array => writeln!(out, "{}[A] {:?}", indent, self.name),
+            ObjectKind::Dictionary => writeln!(out, "{}[D] {:?}", indent, self.name),
+            ObjectKind::Structure => writeln!(out, "{}[S] {:?}", indent, self.name),
+            ObjectKind::Reference => writeln!(
+                out,
+                "{}[REF] {:?} reference to {}",
+                indent,
+                self.name,
+                self.reference.as_deref().unwrap_or("NULL")
+            ),
+        };
+
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+}
+
+impl fmt::Display for ModuleNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(DumpFormat::Text))
+    }
+}
@@ -1,5 +1,74 @@
+use std::fmt::Display;
 use thiserror::Error;
 
+/// Severity of the YARA compiler diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Error or warning reported by the YARA compiler.
+///
+/// ```
+/// # use yari_sys::error::{Diagnostic, Severity};
+/// let diagnostic = Diagnostic {
+///     severity: Severity::Warning,
+///     file: None,
+///     line: 4,
+///     rule: Some("r".to_string()),
+///     message: "string \"$s00\" may slow down scanning".to_string(),
+/// };
+/// assert_eq!(
+///     diagnostic.to_string(),
+///     "line 4: warning: string \"$s00\" may slow down scanning"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// File the diagnostic belongs to, `None` for rules compiled from a string
+    pub file: Option<String>,
+    pub line: i32,
+    /// Rule the diagnostic belongs to, if it was reported inside of a rule
+    pub rule: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}({})", file, self.line)?,
+            None => write!(f, "line {}", self.line)?,
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// Join the diagnostics into one message, one diagnostic per line.
+fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum YariError {
     #[error("cannot parse the expression")]
@@ -20,9 +89,21 @@ pub enum YariError {
     #[error("context builder error: '{}'", .0)]
     ContextBuilderError(String),
 
+    #[error("sample error: '{}'", .0)]
+    SampleError(String),
+
     #[error("cannot evaluate expression because of missing rule context")]
     RuleMissingError,
 
+    #[error("cannot evaluate expression because the rule source is not available (rules were loaded compiled)")]
+    RuleSourceMissingError,
+
+    #[error("cannot load compiled rules: '{}'", .0)]
+    RulesLoadError(String),
+
+    #[error("cannot disassemble unknown opcode {:#04x} at offset {:#06x}", .0, .1)]
+    DisassemblerError(u8, usize),
+
     #[error("cannot evaluate undeclared string")]
     UndeclaredStringError,
 
@@ -32,6 +113,9 @@ pub enum YariError {
     #[error("this type cannot be used as bool")]
     BoolConversionError,
 
+    #[error("cannot compile rules:\n{}", format_diagnostics(.0))]
+    CompileError(Vec<Diagnostic>),
+
     #[error("unknown data store error")]
     Unknown,
 }
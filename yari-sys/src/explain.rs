@@ -0,0 +1,102 @@
+use crate::YrValue;
+use std::fmt;
+
+/// Result of a sub-expression of the explained condition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Value(YrValue),
+    /// Evaluation of the sub-expression failed
+    Error(String),
+    /// Sub-expression was not evaluated, because the other operand decided the result
+    ShortCircuited,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Value(value) if value.is_undefined() => write!(f, "undefined (!)"),
+            Outcome::Value(YrValue::Integer(i)) => write!(f, "{}", i),
+            Outcome::Value(YrValue::Float(v)) => write!(f, "{}", v),
+            Outcome::Value(value @ (YrValue::String(_) | YrValue::Bytes(_))) => write!(
+                f,
+                "\"{}\"",
+                YrValue::escape_bytes(value.as_bytes().unwrap_or_default())
+            ),
+            Outcome::Value(value) => write!(f, "{:?}", value),
+            Outcome::Error(e) => write!(f, "error: {}", e),
+            Outcome::ShortCircuited => write!(f, "short-circuited"),
+        }
+    }
+}
+
+/// Rule condition annotated with the values of all its sub-expressions.
+///
+/// Every node holds the sub-expression formatted as a YARA condition, its outcome and the
+/// operands it was evaluated from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub expression: String,
+    pub outcome: Outcome,
+    pub children: Vec<Explanation>,
+}
+
+impl Explanation {
+    /// Value of the node, `None` if it was not evaluated or the evaluation failed.
+    pub fn value(&self) -> Option<&YrValue> {
+        match &self.outcome {
+            Outcome::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, prefix: &str) -> fmt::Result {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            writeln!(
+                f,
+                "{}{}{} => {}",
+                prefix, branch, child.expression, child.outcome
+            )?;
+            child.fmt_tree(f, &format!("{}{}", prefix, indent))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Explanation {
+    /// Format the explanation as a tree.
+    ///
+    /// ```
+    /// # use yari_sys::explain::{Explanation, Outcome};
+    /// # use yari_sys::YrValue;
+    /// let explanation = Explanation {
+    ///     expression: "$a and $b".to_string(),
+    ///     outcome: Outcome::Value(YrValue::Integer(0)),
+    ///     children: vec![
+    ///         Explanation {
+    ///             expression: "$a".to_string(),
+    ///             outcome: Outcome::Value(YrValue::Integer(0)),
+    ///             children: vec![],
+    ///         },
+    ///         Explanation {
+    ///             expression: "$b".to_string(),
+    ///             outcome: Outcome::ShortCircuited,
+    ///             children: vec![],
+    ///         },
+    ///     ],
+    /// };
+    /// assert_eq!(
+    ///     explanation.to_string(),
+    ///     "$a and $b => 0\n├── $a => 0\n└── $b => short-circuited\n"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} => {}", self.expression, self.outcome)?;
+        self.fmt_tree(f, "")
+    }
+}
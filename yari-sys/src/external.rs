@@ -0,0 +1,83 @@
+use crate::error::YariError;
+use crate::yr_value::YrValue;
+
+/// Value of an external variable defined for the compiler and scanner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExternalVariable {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+}
+
+impl ExternalVariable {
+    /// Check if `name` can be used as an identifier of an external variable.
+    ///
+    /// ```
+    /// # use yari_sys::ExternalVariable;
+    /// assert!(ExternalVariable::is_valid_identifier("_my_var1"));
+    /// assert!(!ExternalVariable::is_valid_identifier("1var"));
+    /// assert!(!ExternalVariable::is_valid_identifier("my-var"));
+    /// assert!(!ExternalVariable::is_valid_identifier(""));
+    /// ```
+    pub fn is_valid_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
+
+impl std::str::FromStr for ExternalVariable {
+    type Err = YariError;
+
+    /// Parse the value the same way as `yara` binary does for the `-d` option.
+    ///
+    /// Values `true` and `false` are booleans, numbers are integers or floats. Everything else is
+    /// a string, optionally enclosed in double quotes.
+    ///
+    /// ```
+    /// use yari_sys::ExternalVariable;
+    ///
+    /// assert_eq!("true".parse(), Ok(ExternalVariable::Boolean(true)));
+    /// assert_eq!("-10".parse(), Ok(ExternalVariable::Integer(-10)));
+    /// assert_eq!("1.5".parse(), Ok(ExternalVariable::Float(1.5)));
+    /// assert_eq!("nan".parse(), Ok(ExternalVariable::String("nan".to_string())));
+    /// assert_eq!("abc".parse(), Ok(ExternalVariable::String("abc".to_string())));
+    /// assert_eq!("\"10\"".parse(), Ok(ExternalVariable::String("10".to_string())));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "true" || s == "false" {
+            return Ok(ExternalVariable::Boolean(s == "true"));
+        }
+
+        if let Ok(i) = s.parse::<i64>() {
+            return Ok(ExternalVariable::Integer(i));
+        }
+
+        // Only plain decimal numbers are floats, `inf` or `nan` are strings
+        if s.chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == '-')
+        {
+            if let Ok(f) = s.parse::<f64>() {
+                return Ok(ExternalVariable::Float(f));
+            }
+        }
+
+        let s = s
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(s);
+        Ok(ExternalVariable::String(s.to_string()))
+    }
+}
+
+impl From<&ExternalVariable> for YrValue {
+    fn from(value: &ExternalVariable) -> Self {
+        match value {
+            ExternalVariable::Integer(i) => YrValue::Integer(*i),
+            ExternalVariable::Float(f) => YrValue::Float(*f),
+            ExternalVariable::Boolean(b) => YrValue::Integer(*b as i64),
+            ExternalVariable::String(s) => YrValue::String(Some(s.clone())),
+        }
+    }
+}
@@ -1,21 +1,41 @@
+pub mod atoms;
 mod bindings;
+pub mod disasm;
+pub mod dump;
 pub mod error;
+pub mod explain;
+pub mod external;
 pub mod module;
 pub mod parser;
+pub mod profile;
+pub mod render;
+pub mod rules;
+pub mod sample;
+pub mod schema;
 pub mod utils;
 pub mod yr_value;
 
 extern crate regex;
 
+use crate::atoms::{automaton_atoms, Atom, StringAtoms};
 use crate::bindings::yr_arena_ref_to_ptr;
+use crate::bindings::yr_atoms_heuristic_quality;
 use crate::bindings::yr_calloc;
 use crate::bindings::yr_compiler_add_string;
 use crate::bindings::yr_compiler_create;
+use crate::bindings::yr_compiler_define_boolean_variable;
+use crate::bindings::yr_compiler_define_float_variable;
+use crate::bindings::yr_compiler_define_integer_variable;
+use crate::bindings::yr_compiler_define_string_variable;
 use crate::bindings::yr_compiler_destroy;
 use crate::bindings::yr_compiler_get_rules;
+use crate::bindings::yr_compiler_set_callback;
+use crate::bindings::yr_compiler_set_include_callback;
 use crate::bindings::yr_filemap_map;
 use crate::bindings::yr_filemap_unmap;
 use crate::bindings::yr_finalize;
+#[cfg(feature = "profiling")]
+use crate::bindings::yr_free;
 use crate::bindings::yr_get_configuration;
 use crate::bindings::yr_hash_table_create;
 use crate::bindings::yr_hash_table_lookup;
@@ -27,15 +47,29 @@ use crate::bindings::yr_notebook_destroy;
 use crate::bindings::yr_object_array_get_item;
 use crate::bindings::yr_re_compile;
 use crate::bindings::yr_rules_destroy;
+use crate::bindings::yr_rules_load;
+use crate::bindings::yr_rules_save;
 use crate::bindings::yr_scan_verify_match;
 use crate::bindings::yr_scanner_create;
+use crate::bindings::yr_scanner_define_boolean_variable;
+use crate::bindings::yr_scanner_define_float_variable;
+use crate::bindings::yr_scanner_define_integer_variable;
+use crate::bindings::yr_scanner_define_string_variable;
 use crate::bindings::yr_scanner_destroy;
+#[cfg(feature = "profiling")]
+use crate::bindings::yr_scanner_get_profiling_info;
+#[cfg(feature = "profiling")]
+use crate::bindings::yr_scanner_reset_profiling_info;
 use crate::bindings::yr_scanner_scan_mem_blocks;
 use crate::bindings::yr_scanner_set_callback;
 use crate::bindings::yr_scanner_set_flags;
 use crate::bindings::yr_scanner_set_timeout;
 use crate::bindings::CALLBACK_MSG_IMPORT_MODULE;
+use crate::bindings::ERROR_CORRUPT_FILE;
+use crate::bindings::ERROR_COULD_NOT_OPEN_FILE;
+use crate::bindings::ERROR_INVALID_FILE;
 use crate::bindings::ERROR_SUCCESS;
+use crate::bindings::ERROR_UNSUPPORTED_FILE_VERSION;
 use crate::bindings::OBJECT_TYPE_ARRAY;
 use crate::bindings::OBJECT_TYPE_DICTIONARY;
 use crate::bindings::OBJECT_TYPE_FLOAT;
@@ -46,8 +80,10 @@ use crate::bindings::OBJECT_TYPE_STRUCTURE;
 use crate::bindings::RE;
 use crate::bindings::RE_ERROR;
 use crate::bindings::SIZED_STRING;
+use crate::bindings::YR_AC_MATCH;
 use crate::bindings::YR_ARENA_REF;
 use crate::bindings::YR_ARRAY_ITERATOR;
+use crate::bindings::YR_ATOM;
 use crate::bindings::YR_COMPILER;
 use crate::bindings::YR_DICT_ITERATOR;
 use crate::bindings::YR_MAPPED_FILE;
@@ -63,17 +99,37 @@ use crate::bindings::YR_OBJECT_DICTIONARY;
 use crate::bindings::YR_OBJECT_FUNCTION;
 use crate::bindings::YR_OBJECT_STRUCTURE;
 use crate::bindings::YR_RULE;
+use crate::bindings::YR_RULES;
 use crate::bindings::YR_SCANNER;
 use crate::bindings::YR_SCAN_CONTEXT;
 use crate::bindings::YR_STRING;
 use crate::bindings::YR_STRUCTURE_MEMBER;
 pub use crate::bindings::YR_UNDEFINED;
 use crate::bindings::YR_VALUE;
+use crate::disasm::{Instruction, Symbols};
+use crate::dump::ObjectKind;
+pub use crate::dump::{DumpFormat, ModuleNode};
 pub use crate::error::YariError;
+use crate::error::{Diagnostic, Severity};
+pub use crate::explain::Explanation;
+use crate::explain::Outcome;
+pub use crate::external::ExternalVariable;
 pub use crate::module::Module;
 pub use crate::module::MODULES;
-use crate::parser::{parse, Argument, Expression};
-use crate::utils::expression_to_rules_with_condition;
+use crate::parser::{parse, Argument, BinaryOperator, Expression, Quantifier};
+pub use crate::profile::Profile;
+use crate::profile::{RuleProfile, StringProfile, StringStats};
+pub use crate::render::Renderer;
+pub use crate::rules::Rule;
+pub use crate::rules::RuleSource;
+pub use crate::rules::StringMatch;
+use crate::rules::{chain_parts, include_path, qualified_name, string_identifiers};
+pub use crate::sample::Sample;
+pub use crate::schema::Schema;
+use crate::utils::{
+    expression_to_rules_with_condition, rule_condition, rules_contain_rule, rules_imports,
+    unescape_string,
+};
 pub use crate::yr_value::YrValue;
 use core::ffi::c_void;
 use log::{debug, error};
@@ -84,11 +140,15 @@ use std::collections::HashMap;
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::fmt::Debug;
+use std::io::Read;
 use std::mem::{size_of, ManuallyDrop};
+use std::os::raw::c_char;
 use std::path::Path;
 use std::path::PathBuf;
 use std::ptr;
 use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
 
 #[cfg(feature = "avast")]
 use crate::bindings::OBJECT_TYPE_REFERENCE;
@@ -124,8 +184,21 @@ impl YR_OBJECT_STRUCTURE {
     pub fn members(&self) -> YrStructureMemberIterator {
         YrStructureMemberIterator::new(self.members)
     }
+
+    /// Members in the order they were declared by the module.
+    ///
+    /// libyara prepends new members to the list, so `members` yields them in reverse.
+    pub fn declared_members(&self) -> Vec<*mut YR_OBJECT> {
+        let mut members: Vec<_> = self.members().collect();
+        members.reverse();
+        members
+    }
 }
 
+/// Guard of `yr_initialize` and `yr_finalize`, libyara counts the initializations without
+/// synchronization and contexts can be created and dropped from multiple threads.
+static YR_INIT_LOCK: Mutex<()> = Mutex::new(());
+
 pub fn object_type_to_string(object_type: i8) -> &'static str {
     match object_type {
         1 => "integer",
@@ -280,15 +353,45 @@ pub unsafe extern "C" fn _yr_scanner_scan_mem_block(
     scanner: *mut YR_SCANNER,
     block_data: *const u8,
     block: *mut YR_MEMORY_BLOCK,
+) {
+    scan_mem_block(scanner, block_data, block, None)
+}
+
+/// Scan the memory block for the rule strings, collect the statistics of every string into
+/// `string_stats` (indexed by the string index) if they are provided.
+unsafe fn scan_mem_block(
+    scanner: *mut YR_SCANNER,
+    block_data: *const u8,
+    block: *mut YR_MEMORY_BLOCK,
+    mut string_stats: Option<&mut [StringStats]>,
 ) {
     let block = *block;
-    let rules = (*scanner).rules;
+
+    ac_scan((*scanner).rules, block_data, block.size, |offset, m| {
+        let start = string_stats.is_some().then(Instant::now);
+        let res = yr_scan_verify_match(scanner, m, block_data, block.size, block.base, offset);
+
+        if let (Some(stats), Some(start)) = (string_stats.as_deref_mut(), start) {
+            let stats = &mut stats[(*(*m).__bindgen_anon_1.string).idx as usize];
+            stats.atom_matches += 1;
+            stats.match_time += start.elapsed();
+        }
+        res
+    });
+}
+
+/// Run the Aho-Corasick automaton of the `rules` over the data and call `on_match` with the
+/// offset of every atom match which has to be verified. The scan stops when `on_match` fails.
+unsafe fn ac_scan<F>(rules: *const YR_RULES, block_data: *const u8, size: usize, mut on_match: F)
+where
+    F: FnMut(usize, *mut YR_AC_MATCH) -> i32,
+{
     let transition_table = (*rules).ac_transition_table;
     let match_table = (*rules).ac_match_table;
     let mut i = 0;
     let mut state: u32 = 0; // YR_AC_ROOT_STATE
 
-    while i < block.size {
+    while i < size {
         if *match_table.add(state.try_into().unwrap()) != 0 {
             let mut m = (*rules).ac_match_pool.add(
                 (*match_table.add(state.try_into().unwrap()) - 1)
@@ -298,14 +401,7 @@ pub unsafe extern "C" fn _yr_scanner_scan_mem_block(
 
             while !m.is_null() {
                 if (*m).backtrack as usize <= i {
-                    let res = yr_scan_verify_match(
-                        scanner,
-                        m,
-                        block_data,
-                        block.size,
-                        block.base,
-                        i - (*m).backtrack as usize,
-                    );
+                    let res = on_match(i - (*m).backtrack as usize, m);
                     if res != ERROR_SUCCESS as i32 {
                         error!("Call to yr_scan_verify_match failed with {}", res);
                         return;
@@ -338,14 +434,7 @@ pub unsafe extern "C" fn _yr_scanner_scan_mem_block(
 
         while !m.is_null() {
             if (*m).backtrack as usize <= i {
-                yr_scan_verify_match(
-                    scanner,
-                    m,
-                    block_data,
-                    block.size,
-                    block.base,
-                    i - (*m).backtrack as usize,
-                );
+                on_match(i - (*m).backtrack as usize, m);
             }
             m = (*m).__bindgen_anon_4.next;
         }
@@ -404,12 +493,7 @@ pub extern "C" fn rule_match_callback(
             // Match found
             let context = user_data.cast::<Context>();
             let rule = message_data.cast::<YR_RULE>();
-            let rule_identifier = unsafe {
-                CStr::from_ptr((*rule).__bindgen_anon_1.identifier)
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-            };
+            let rule_identifier = unsafe { qualified_rule_name(rule) };
 
             unsafe { (*context).rules_matching.push(rule_identifier) };
         }
@@ -417,12 +501,7 @@ pub extern "C" fn rule_match_callback(
             // No match found
             let context = user_data.cast::<Context>();
             let rule = message_data.cast::<YR_RULE>();
-            let rule_identifier = unsafe {
-                CStr::from_ptr((*rule).__bindgen_anon_1.identifier)
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-            };
+            let rule_identifier = unsafe { qualified_rule_name(rule) };
 
             unsafe { (*context).rules_not_matching.push(rule_identifier) };
         }
@@ -431,6 +510,160 @@ pub extern "C" fn rule_match_callback(
     0 // OK
 }
 
+/// Get the rule name qualified by its namespace (`namespace:rule`).
+///
+/// # Safety
+/// Caller must ensure that the rule is valid.
+unsafe fn qualified_rule_name(rule: *const YR_RULE) -> String {
+    let identifier = CStr::from_ptr((*rule).__bindgen_anon_1.identifier).to_string_lossy();
+    let namespace =
+        CStr::from_ptr((*(*rule).__bindgen_anon_5.ns).__bindgen_anon_1.name).to_string_lossy();
+    format!("{}:{}", namespace, identifier)
+}
+
+/// Rule source read from the `path`, `None` if the file cannot be read.
+fn read_rule_file(namespace: Option<String>, path: PathBuf) -> Option<RuleSource> {
+    let source = std::fs::read_to_string(&path).ok()?;
+    Some(RuleSource {
+        namespace,
+        path: Some(path),
+        source,
+    })
+}
+
+/// Map the sample file into memory.
+fn map_file(path: &Path) -> Result<YR_MAPPED_FILE, YariError> {
+    if !path.exists() {
+        return Err(YariError::SampleError(format!("{:?} no such file", path)));
+    }
+    if !path.is_file() {
+        return Err(YariError::SampleError(format!("{:?} is not a file", path)));
+    }
+
+    let filename = path
+        .to_str()
+        .and_then(|path| CString::new(path).ok())
+        .ok_or_else(|| YariError::SampleError(format!("{:?} is not a valid path", path)))?;
+
+    let mut mfile = YR_MAPPED_FILE::default();
+    if unsafe { yr_filemap_map(filename.as_ptr(), &mut mfile) } != ERROR_SUCCESS as i32 {
+        return Err(YariError::SampleError(format!("cannot map {:?}", path)));
+    }
+    Ok(mfile)
+}
+
+/// Value of the string external variable `name` as a C string.
+fn external_string_cstr(name: &str, value: &str) -> Result<CString, YariError> {
+    CString::new(value).map_err(|_| {
+        YariError::ContextBuilderError(format!(
+            "value of the external variable {:?} contains NUL",
+            name
+        ))
+    })
+}
+
+/// State shared with the compiler callbacks.
+#[derive(Debug, Default)]
+struct CompilerState {
+    /// Errors and warnings reported by the compiler
+    diagnostics: Vec<Diagnostic>,
+    /// File of the rule source being compiled
+    current_file: Option<PathBuf>,
+    /// Resolved paths of the included files (YARA reports includes by the name used in the
+    /// include directive)
+    includes: HashMap<String, PathBuf>,
+}
+
+/// Compiler callback collecting errors and warnings into `CompilerState` passed as `user_data`.
+extern "C" fn compiler_callback(
+    error_level: i32,
+    file_name: *const c_char,
+    line_number: i32,
+    rule: *const YR_RULE,
+    message: *const c_char,
+    user_data: *mut c_void,
+) {
+    let state = unsafe { &mut *user_data.cast::<CompilerState>() };
+    let to_string = |ptr: *const c_char| {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let diagnostic = Diagnostic {
+        // YARA_ERROR_LEVEL_ERROR = 0, YARA_ERROR_LEVEL_WARNING = 1
+        severity: if error_level == 0 {
+            Severity::Error
+        } else {
+            Severity::Warning
+        },
+        file: if file_name.is_null() {
+            state
+                .current_file
+                .as_ref()
+                .map(|f| f.to_string_lossy().into_owned())
+        } else {
+            Some(to_string(file_name))
+        },
+        line: line_number,
+        rule: (!rule.is_null()).then(|| to_string(unsafe { (*rule).__bindgen_anon_1.identifier })),
+        message: to_string(message),
+    };
+    debug!("Compiler diagnostic {:?}", diagnostic);
+
+    state.diagnostics.push(diagnostic);
+}
+
+/// Include callback resolving included files relative to the including file.
+extern "C" fn include_callback(
+    include_name: *const c_char,
+    calling_rule_filename: *const c_char,
+    _calling_rule_namespace: *const c_char,
+    user_data: *mut c_void,
+) -> *const c_char {
+    let state = unsafe { &mut *user_data.cast::<CompilerState>() };
+    let include_name = unsafe { CStr::from_ptr(include_name) }
+        .to_string_lossy()
+        .into_owned();
+
+    let calling_file = if calling_rule_filename.is_null() {
+        state.current_file.clone()
+    } else {
+        let calling_rule_filename = unsafe { CStr::from_ptr(calling_rule_filename) }
+            .to_string_lossy()
+            .into_owned();
+        Some(
+            state
+                .includes
+                .get(&calling_rule_filename)
+                .cloned()
+                .unwrap_or_else(|| PathBuf::from(calling_rule_filename)),
+        )
+    };
+
+    let path = include_path(calling_file.as_deref(), &include_name);
+    debug!("Including {:?} as {:?}", include_name, path);
+
+    match std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|source| CString::new(source).ok())
+    {
+        Some(source) => {
+            state.includes.insert(include_name, path);
+            source.into_raw()
+        }
+        // Compiler reports the error
+        None => ptr::null(),
+    }
+}
+
+/// Free the included file source returned by `include_callback`.
+extern "C" fn include_free(callback_result_ptr: *const c_char, _user_data: *mut c_void) {
+    if !callback_result_ptr.is_null() {
+        drop(unsafe { CString::from_raw(callback_result_ptr as *mut c_char) });
+    }
+}
+
 /// YARA evaluation context.
 ///
 /// ```
@@ -456,55 +689,187 @@ pub struct Context {
     iterator: Box<YR_MEMORY_BLOCK_ITERATOR>,
     block: Box<YR_MEMORY_BLOCK>,
 
-    input: PathBuf,
-    rule_string: Option<String>,
+    sample: Sample,
+    /// Mapping of the sample file, `None` for in-memory samples
+    sample_mapped_file: Option<YR_MAPPED_FILE>,
+    rule_sources: Vec<RuleSource>,
+    /// Rules were loaded in the compiled form, so their sources are not available
+    compiled_rules: bool,
+    externals: HashMap<String, ExternalVariable>,
+    /// State of the compiler callbacks (boxed, so the compiler can keep a pointer to it)
+    compiler_state: Box<CompilerState>,
     fallback_scanner: *mut YR_SCANNER,
     use_fallback_eval: bool,
+    /// Statistics of the strings collected by the last profiled scan
+    string_stats: Vec<StringStats>,
+    /// Results of the last profiled scan, `None` if the profiling is not enabled
+    profile: Option<Profile>,
+    /// Scanner computing the rule costs, created by the first profiled scan
+    #[cfg(feature = "profiling")]
+    profiling_scanner: *mut YR_SCANNER,
 }
 
 /// Builder to create a new YARA context.
 #[derive(Debug, Default)]
 pub struct ContextBuilder {
-    rule_string: Option<String>,
-    sample: Option<PathBuf>,
+    rule_sources: Vec<RuleSource>,
+    compiled_rules: Option<PathBuf>,
+    sample: Option<Sample>,
     module_data: HashMap<Module, PathBuf>,
+    externals: HashMap<String, ExternalVariable>,
+    profiling: bool,
 }
 
 impl ContextBuilder {
     /// Set builder to use `rule_string` as rule for context.
     ///
-    /// If `None` is supplied, load bare context.
-    pub fn with_rule_string<P: Into<String>>(mut self, rule_string: Option<P>) -> Self {
-        self.rule_string = rule_string.map(|p| p.into());
+    /// Replaces the rules previously set for the default namespace. If `None` is supplied,
+    /// load bare context.
+    pub fn with_rule_string<P: Into<String>>(self, rule_string: Option<P>) -> Self {
+        let source = rule_string.map(|rule_string| RuleSource {
+            namespace: None,
+            path: None,
+            source: rule_string.into(),
+        });
+        self.with_default_rules(source)
+    }
+
+    /// Set builder to use `rule_string` as rules in the namespace `ns`.
+    pub fn with_rule_string_ns<N: Into<String>, P: Into<String>>(
+        mut self,
+        ns: N,
+        rule_string: P,
+    ) -> Self {
+        self.rule_sources.push(RuleSource {
+            namespace: Some(ns.into()),
+            path: None,
+            source: rule_string.into(),
+        });
         self
     }
 
     /// Set builder to use content of `rule_file` as rule for context.
     ///
-    /// If `None` is supplied, load bare context.
-    pub fn with_rule_file<P: Into<String>>(mut self, rule_file: Option<P>) -> Self {
-        if let Some(rule_file) = rule_file {
-            if let Ok(rule_string) = std::fs::read_to_string(rule_file.into()) {
-                self.rule_string = Some(rule_string);
-            }
+    /// Replaces the rules previously set for the default namespace. If `None` is supplied or the
+    /// file cannot be read, the rules are kept.
+    pub fn with_rule_file<P: Into<String>>(self, rule_file: Option<P>) -> Self {
+        match rule_file.and_then(|rule_file| read_rule_file(None, rule_file.into().into())) {
+            Some(source) => self.with_default_rules(Some(source)),
+            None => self,
+        }
+    }
+
+    /// Set builder to use content of `rule_file` as rules in the namespace `ns`.
+    ///
+    /// Included files are resolved relative to the `rule_file`.
+    pub fn with_rule_file_ns<N: Into<String>, P: AsRef<Path>>(
+        mut self,
+        ns: N,
+        rule_file: P,
+    ) -> Self {
+        if let Some(source) = read_rule_file(Some(ns.into()), rule_file.as_ref().to_owned()) {
+            self.rule_sources.push(source);
         }
         self
     }
 
+    fn with_default_rules(mut self, source: Option<RuleSource>) -> Self {
+        self.rule_sources
+            .retain(|source| source.namespace.is_some());
+        self.rule_sources.extend(source);
+        self
+    }
+
+    /// Set builder to load rules compiled by `yarac` from `path`.
+    ///
+    /// Compiled rules cannot be combined with rule sources. Some expressions evaluated in the
+    /// rule context need the rule source and fail with [`YariError::RuleSourceMissingError`].
+    pub fn with_compiled_rules<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.compiled_rules = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Set builder to profile the scan of the sample.
+    ///
+    /// The rules are scanned with profiling enabled and the cost of every rule and string is
+    /// available in [`Context::profile`]. The libyara rule cost is reported only with the
+    /// `profiling` feature, which requires libyara built with `--enable-profiling`.
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
     /// Set builder to use `sample` as input.
     ///
     /// If `None` is supplied use /dev/null as sample.
     pub fn with_sample<P: Into<PathBuf>>(mut self, sample: Option<P>) -> Self {
-        self.sample = sample.map(|p| p.into());
+        self.sample = sample.map(|p| Sample::File(p.into()));
         self
     }
 
+    /// Set builder to use in-memory `bytes` as input.
+    pub fn with_sample_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.sample = Some(Sample::Bytes(bytes));
+        self
+    }
+
+    /// Set builder to use all the data read from `reader` as input.
+    pub fn with_sample_reader<R: Read>(self, mut reader: R) -> Result<Self, YariError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| YariError::ContextBuilderError(format!("cannot read sample: {}", e)))?;
+        Ok(self.with_sample_bytes(bytes))
+    }
+
     /// Register a `module`, `data` pair with builder.
     pub fn with_module_data<P: AsRef<Path>>(mut self, module: Module, data: P) -> Self {
         self.module_data.insert(module, data.as_ref().to_owned());
         self
     }
 
+    /// Define external variable `name` for the compiler and scanner.
+    ///
+    /// Defining the same variable again replaces the previous value.
+    pub fn with_external<P: Into<String>>(mut self, name: P, value: ExternalVariable) -> Self {
+        self.externals.insert(name.into(), value);
+        self
+    }
+
+    /// Define integer external variable `name`.
+    pub fn with_external_int<P: Into<String>>(self, name: P, value: i64) -> Self {
+        self.with_external(name, ExternalVariable::Integer(value))
+    }
+
+    /// Define float external variable `name`.
+    pub fn with_external_float<P: Into<String>>(self, name: P, value: f64) -> Self {
+        self.with_external(name, ExternalVariable::Float(value))
+    }
+
+    /// Define boolean external variable `name`.
+    pub fn with_external_bool<P: Into<String>>(self, name: P, value: bool) -> Self {
+        self.with_external(name, ExternalVariable::Boolean(value))
+    }
+
+    /// Define string external variable `name`.
+    pub fn with_external_string<P: Into<String>, S: Into<String>>(self, name: P, value: S) -> Self {
+        self.with_external(name, ExternalVariable::String(value.into()))
+    }
+
+    /// Parse the external variable definition string.
+    ///
+    /// String format is expected in the same format as uses `yara` binary for the `-d` option.
+    /// That is following: `<name>=<value>` (e.g `debug=true`). Type of the variable is derived
+    /// from the value, see [`ExternalVariable`].
+    pub fn parse_external_variable_str(definition: &str) -> Option<(String, ExternalVariable)> {
+        let (name, value) = definition.split_once('=')?;
+        if !ExternalVariable::is_valid_identifier(name) {
+            return None;
+        }
+
+        Some((name.to_owned(), value.parse().ok()?))
+    }
+
     /// Parse the module data string.
     ///
     /// String format is expected in the same format as uses `yara` binary. That is following:
@@ -524,7 +889,7 @@ impl ContextBuilder {
 
     /// Consume builder and create new YARA context struct.
     pub fn build(self) -> Result<Context, YariError> {
-        if let Some(sample) = &self.sample {
+        if let Some(Sample::File(sample)) = &self.sample {
             if !sample.exists() {
                 return Err(YariError::ContextBuilderError(format!(
                     "{:?} no such file",
@@ -533,7 +898,33 @@ impl ContextBuilder {
             }
         }
 
-        let mut context = Context::new(self.sample, self.rule_string, false);
+        for (name, value) in &self.externals {
+            if !ExternalVariable::is_valid_identifier(name) {
+                return Err(YariError::ContextBuilderError(format!(
+                    "{:?} is not a valid external variable name",
+                    name
+                )));
+            }
+            // libyara takes the value as a C string, it would be silently cut at the NUL
+            if let ExternalVariable::String(s) = value {
+                external_string_cstr(name, s)?;
+            }
+        }
+
+        if self.compiled_rules.is_some() && !self.rule_sources.is_empty() {
+            return Err(YariError::ContextBuilderError(
+                "compiled rules cannot be combined with rule sources".to_string(),
+            ));
+        }
+
+        let mut context = Context::new(self.sample, self.rule_sources, self.externals, false)?;
+        if context.diagnostics().iter().any(Diagnostic::is_error) {
+            return Err(YariError::CompileError(context.diagnostics().to_vec()));
+        }
+
+        if let Some(compiled_rules) = self.compiled_rules {
+            context.load_compiled_rules(&compiled_rules)?;
+        }
 
         for (module, data) in self.module_data {
             if !data.exists() {
@@ -545,19 +936,30 @@ impl ContextBuilder {
             context.with_module_data(module, data);
         }
 
+        if self.profiling {
+            context.run_profiling();
+        }
+
         Ok(context)
     }
 }
 
 impl Context {
-    pub fn new<P: AsRef<Path> + From<String>>(
-        input: Option<P>,
-        rule_string: Option<String>,
+    /// Create the context and compile the `rule_sources`.
+    ///
+    /// Fails if the rule sources contain NUL, the compiled rules can not be created or the
+    /// external variables can not be defined. Compiler errors are reported in
+    /// [`Context::diagnostics`].
+    pub fn new(
+        sample: Option<Sample>,
+        rule_sources: Vec<RuleSource>,
+        externals: HashMap<String, ExternalVariable>,
         use_fallback_eval: bool,
-    ) -> Context {
-        let input_file = input.unwrap_or_else(|| P::from("/dev/null".to_owned()));
-
-        unsafe { yr_initialize() };
+    ) -> Result<Context, YariError> {
+        {
+            let _guard = YR_INIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            unsafe { yr_initialize() };
+        }
 
         let mut res = Context {
             context: ManuallyDrop::new(Box::default()),
@@ -570,10 +972,18 @@ impl Context {
             rules_not_matching: Vec::new(),
             iterator: Box::default(),
             block: Box::default(),
-            input: PathBuf::new(),
-            rule_string: rule_string.clone(),
+            sample: sample.unwrap_or_default(),
+            sample_mapped_file: None,
+            rule_sources,
+            compiled_rules: false,
+            externals,
+            compiler_state: Box::default(),
             use_fallback_eval,
             fallback_scanner: ptr::null_mut(),
+            string_stats: Vec::new(),
+            profile: None,
+            #[cfg(feature = "profiling")]
+            profiling_scanner: ptr::null_mut(),
         };
 
         unsafe {
@@ -584,10 +994,22 @@ impl Context {
             )
         };
 
-        res.input.push(input_file.as_ref());
-
+        // Context is finished even if the definition fails, so it can be dropped safely
+        let mut externals_defined: Result<(), YariError>;
         unsafe {
             yr_compiler_create(&mut res.compiler as *mut *mut YR_COMPILER);
+            yr_compiler_set_callback(
+                res.compiler,
+                Some(compiler_callback),
+                &mut *res.compiler_state as *mut _ as *mut c_void,
+            );
+            yr_compiler_set_include_callback(
+                res.compiler,
+                Some(include_callback),
+                Some(include_free),
+                &mut *res.compiler_state as *mut _ as *mut c_void,
+            );
+            externals_defined = res.define_compiler_externals();
 
             res.iterator.context = &mut *res.context as *mut _ as *mut _;
 
@@ -601,57 +1023,281 @@ impl Context {
 
             res.context.profiling_info = ptr::null_mut();
 
-            let mfile = res.filemap(input_file);
-            res.iterator_init(mfile.data, mfile.size);
+            // Sample existence is checked by the builder
+            let (data, size) = res.map_sample().unwrap_or((ptr::null(), 0));
+            res.iterator_init(data, size);
             res.context.iterator = &mut *res.iterator;
+            res.context.entry_point = yr_get_entry_point_offset(data, size);
+
+            let compiled = !res.rule_sources.is_empty()
+                && match res.compile_sources() {
+                    Ok(()) => true,
+                    // Compiler errors are reported in the diagnostics
+                    Err(YariError::CompileError(_)) => false,
+                    Err(e) => return Err(e),
+                };
+            if compiled {
+                if use_fallback_eval {
+                    yr_scanner_create(
+                        res.context.rules,
+                        &mut res.fallback_scanner as *mut *mut YR_SCANNER,
+                    );
+                    yr_scanner_set_callback(
+                        res.fallback_scanner,
+                        Some(rule_match_callback),
+                        (&mut res as *mut Context).cast::<c_void>(),
+                    );
+                    externals_defined =
+                        externals_defined.and(res.define_scanner_externals(res.fallback_scanner));
+                    yr_scanner_set_timeout(res.fallback_scanner, 0);
+                    yr_scanner_set_flags(res.fallback_scanner, 8 | 16); // SCAN_FLAGS_REPORT_RULES_MATCHING | SCAN_FLAGS_REPORT_RULES_NOT_MATCHING
+                    yr_scanner_scan_mem_blocks(res.fallback_scanner, res.iterator.as_mut());
+                } else {
+                    res.setup_scanner();
+                    res.scan_strings();
+                }
+            }
+        }
 
-            if let Some(rules_string) = rule_string {
-                let rules_cstr = CString::new(rules_string).unwrap();
-                if res.compile_string(&rules_cstr).is_ok() {
-                    if use_fallback_eval {
-                        yr_scanner_create(
-                            res.context.rules,
-                            &mut res.fallback_scanner as *mut *mut YR_SCANNER,
-                        );
-                        yr_scanner_set_callback(
-                            res.fallback_scanner,
-                            Some(rule_match_callback),
-                            (&mut res as *mut Context).cast::<c_void>(),
-                        );
-                        yr_scanner_set_timeout(res.fallback_scanner, 0);
-                        yr_scanner_set_flags(res.fallback_scanner, 8 | 16); // SCAN_FLAGS_REPORT_RULES_MATCHING | SCAN_FLAGS_REPORT_RULES_NOT_MATCHING
-                        yr_scanner_scan_mem_blocks(res.fallback_scanner, res.iterator.as_mut());
-                    } else {
-                        res.setup_scanner();
+        externals_defined.map(|()| res)
+    }
 
-                        let first_func = (*res.context.iterator).first.unwrap();
-                        let block = first_func(res.context.iterator);
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::default()
+    }
 
-                        let fetch_data_func = (*block).fetch_data.expect("msg");
-                        let data = fetch_data_func(block);
+    /// Replace the scanned sample without rebuilding the context.
+    ///
+    /// Compiled rules and module data are kept. String matches are scanned again and modules
+    /// imported so far are reloaded with the new sample.
+    pub fn set_sample<S: Into<Sample>>(&mut self, sample: S) -> Result<(), YariError> {
+        let sample = sample.into();
+        debug!("Setting sample {:?}", sample);
+
+        // Map the new sample first, the current one stays loaded if the mapping fails
+        let mapped_file = match &sample {
+            Sample::File(path) => Some(map_file(path)?),
+            Sample::Bytes(_) => None,
+        };
 
-                        let mut max_match_data = 0_usize;
-                        yr_get_configuration(
-                            2, // YR_CONFIG_MAX_MATCH_DATA
-                            (&mut max_match_data as *mut usize).cast::<c_void>(),
-                        );
-                        yr_notebook_create(
-                            1024 * (size_of::<YR_MATCH>() + max_match_data),
-                            &mut res.context.matches_notebook,
-                        );
+        // Unload modules before the old sample data are released
+        let imported_modules: Vec<Module> = self.modules.keys().copied().collect();
+        unsafe { yr_modules_unload_all(&mut **self.context) };
+        self.modules.clear();
+        self.objects.clear();
 
-                        res.context.entry_point = yr_get_entry_point_offset(data, (*block).size);
-                        _yr_scanner_scan_mem_block(&mut **res.context, data, block);
-                    }
-                }
+        self.unmap_sample();
+        self.sample = sample;
+        self.sample_mapped_file = mapped_file;
+
+        let (data, size) = self.sample_data();
+        self.iterator_init(data, size);
+        self.context.entry_point = unsafe { yr_get_entry_point_offset(data, size) };
+
+        // Profiling scans the strings on its own
+        if !self.context.rules.is_null() && self.profile.is_none() {
+            unsafe {
+                self.reset_matches();
+                self.scan_strings();
             }
         }
 
+        for module in imported_modules {
+            self.import_module(module)?;
+        }
+
+        if self.profile.is_some() {
+            self.run_profiling();
+        }
+
+        Ok(())
+    }
+
+    /// Load rules compiled by `yarac` from `path` and scan the sample for their strings.
+    fn load_compiled_rules(&mut self, path: &Path) -> Result<(), YariError> {
+        debug!("Loading compiled rules {:?}", path);
+        let filename = path
+            .to_str()
+            .and_then(|p| CString::new(p).ok())
+            .ok_or_else(|| YariError::RulesLoadError(format!("{:?} is not a valid path", path)))?;
+
+        let res = unsafe { yr_rules_load(filename.as_ptr(), &mut self.context.rules) };
+        if res != ERROR_SUCCESS as i32 {
+            self.context.rules = ptr::null_mut();
+            let reason = match res as u32 {
+                ERROR_COULD_NOT_OPEN_FILE => "cannot open file",
+                ERROR_INVALID_FILE => "not a compiled rules file",
+                ERROR_CORRUPT_FILE => "file is corrupted",
+                ERROR_UNSUPPORTED_FILE_VERSION => "unsupported file version",
+                _ => "unknown error",
+            };
+            return Err(YariError::RulesLoadError(format!("{:?} {}", path, reason)));
+        }
+        self.compiled_rules = true;
+
+        unsafe {
+            self.setup_scanner();
+            self.scan_strings();
+        }
+        Ok(())
+    }
+
+    /// Save the compiled rules to `path`, the file can be loaded with
+    /// [`ContextBuilder::with_compiled_rules`] or used by `yara -C`.
+    pub fn save_compiled_rules<P: AsRef<Path>>(&self, path: P) -> Result<(), YariError> {
+        let path = path.as_ref();
+        if self.context.rules.is_null() {
+            return Err(YariError::RuleMissingError);
+        }
+
+        let filename = path
+            .to_str()
+            .and_then(|p| CString::new(p).ok())
+            .ok_or_else(|| {
+                YariError::ContextBuilderError(format!("{:?} is not a valid path", path))
+            })?;
+        if unsafe { yr_rules_save(self.context.rules, filename.as_ptr()) } != ERROR_SUCCESS as i32 {
+            return Err(YariError::ContextBuilderError(format!(
+                "cannot save compiled rules to {:?}",
+                path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Errors and warnings reported by the compiler.
+    ///
+    /// Context created by [`ContextBuilder::build`] contains only warnings, because it fails on
+    /// compilation errors.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.compiler_state.diagnostics
+    }
+
+    /// Rules compiled into the context with the number of string matches in the sample.
+    pub fn rules(&self) -> Vec<Rule> {
+        if self.context.rules.is_null() {
+            return Vec::new();
+        }
+
+        let rules = unsafe { *self.context.rules };
+
+        #[cfg(not(feature = "avast"))]
+        let rules_table = unsafe { rules.__bindgen_anon_1.rules_table };
+        #[cfg(feature = "avast")]
+        let rules_table = rules.rules_table;
+
+        let matches = |s: *const YR_STRING| unsafe {
+            (*self.context.matches.offset((*s).idx as isize)).count as usize
+        };
+
+        let mut res = Vec::new();
+        let mut i = 0_isize;
+        while (unsafe { *rules_table.offset(i) }).flags != RULE_FLAGS_NULL {
+            res.push(unsafe { Rule::from_yr_rule(&*rules_table.offset(i), matches) });
+            i += 1;
+        }
         res
     }
 
-    pub fn builder() -> ContextBuilder {
-        ContextBuilder::default()
+    /// Rule `name` compiled into the context.
+    ///
+    /// Rule name can be qualified by a namespace (`namespace:rule`), otherwise the first rule
+    /// with the name is returned.
+    pub fn rule(&self, name: &str) -> Option<Rule> {
+        let (namespace, name) = match name.split_once(':') {
+            Some((namespace, name)) => (Some(namespace), name),
+            None => (None, name),
+        };
+
+        self.rules().into_iter().find(|rule| {
+            rule.name == name && namespace.map_or(true, |namespace| namespace == rule.namespace)
+        })
+    }
+
+    /// Modules imported by the rule sources.
+    ///
+    /// Imports are not stored in the compiled rules, so the list is empty for rules loaded by
+    /// [`ContextBuilder::with_compiled_rules`].
+    pub fn imports(&self) -> Vec<String> {
+        let mut imports: Vec<String> = self
+            .rule_sources
+            .iter()
+            .flat_map(|source| rules_imports(&source.source))
+            .collect();
+        imports.sort();
+        imports.dedup();
+        imports
+    }
+
+    /// Define external variables for the compiler, this has to be done before the rules are
+    /// added.
+    unsafe fn define_compiler_externals(&mut self) -> Result<(), YariError> {
+        for (name, value) in &self.externals {
+            let name_cstr = CString::new(name.as_str()).unwrap();
+            let res = match value {
+                ExternalVariable::Integer(i) => {
+                    yr_compiler_define_integer_variable(self.compiler, name_cstr.as_ptr(), *i)
+                }
+                ExternalVariable::Float(f) => {
+                    yr_compiler_define_float_variable(self.compiler, name_cstr.as_ptr(), *f)
+                }
+                ExternalVariable::Boolean(b) => yr_compiler_define_boolean_variable(
+                    self.compiler,
+                    name_cstr.as_ptr(),
+                    *b as i32,
+                ),
+                ExternalVariable::String(s) => {
+                    let value_cstr = external_string_cstr(name, s)?;
+                    yr_compiler_define_string_variable(
+                        self.compiler,
+                        name_cstr.as_ptr(),
+                        value_cstr.as_ptr(),
+                    )
+                }
+            };
+
+            if res != ERROR_SUCCESS as i32 {
+                return Err(YariError::ContextBuilderError(format!(
+                    "cannot define external variable {:?} (error {})",
+                    name, res
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Define external variables for the fallback scanner.
+    unsafe fn define_scanner_externals(&self, scanner: *mut YR_SCANNER) -> Result<(), YariError> {
+        for (name, value) in &self.externals {
+            let name_cstr = CString::new(name.as_str()).unwrap();
+            let res = match value {
+                ExternalVariable::Integer(i) => {
+                    yr_scanner_define_integer_variable(scanner, name_cstr.as_ptr(), *i)
+                }
+                ExternalVariable::Float(f) => {
+                    yr_scanner_define_float_variable(scanner, name_cstr.as_ptr(), *f)
+                }
+                ExternalVariable::Boolean(b) => {
+                    yr_scanner_define_boolean_variable(scanner, name_cstr.as_ptr(), *b as i32)
+                }
+                ExternalVariable::String(s) => {
+                    let value_cstr = external_string_cstr(name, s)?;
+                    yr_scanner_define_string_variable(
+                        scanner,
+                        name_cstr.as_ptr(),
+                        value_cstr.as_ptr(),
+                    )
+                }
+            };
+
+            if res != ERROR_SUCCESS as i32 {
+                return Err(YariError::ContextBuilderError(format!(
+                    "cannot define external variable {:?} (error {})",
+                    name, res
+                )));
+            }
+        }
+        Ok(())
     }
 
     fn with_module_data<P: AsRef<Path>>(&mut self, module: Module, path: P) {
@@ -770,6 +1416,11 @@ impl Context {
         self.objects.get(path)
     }
 
+    /// Paths of the cached objects of the imported modules (e.g. `pe.sections[0].name`).
+    pub fn object_paths(&self) -> impl Iterator<Item = &str> {
+        self.objects.keys().map(String::as_str)
+    }
+
     /// Convert an integer to a new allocated YR_VALUE
     fn i_from_int(&self, i: &i64) -> YR_VALUE {
         // Allocate a new YR_VALUE
@@ -781,8 +1432,18 @@ impl Context {
         *ptr
     }
 
-    fn str_from_str(&self, s: &str) -> YR_VALUE {
-        // let c_string = CString::new(s).expect("Cannot convert value to CString");
+    /// Convert a float to a new allocated YR_VALUE
+    fn f_from_float(&self, f: &f64) -> YR_VALUE {
+        // Allocate a new YR_VALUE
+        let ptr: &mut YR_VALUE = Box::leak(Box::default());
+
+        // Set the value
+        ptr.d = *f;
+
+        *ptr
+    }
+
+    fn str_from_bytes(&self, s: &[u8]) -> YR_VALUE {
         let struct_size = Layout::new::<SIZED_STRING>();
         let data_size = Layout::array::<i8>(s.len() + 1).unwrap();
         let (layout, _) = struct_size.extend(data_size).unwrap();
@@ -795,15 +1456,15 @@ impl Context {
         };
 
         // Copy the string
-        for (i, c) in s.bytes().enumerate() {
+        for (i, c) in s.iter().enumerate() {
             #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
             unsafe {
-                (*sized_string).c_string.as_mut_ptr().add(i).write(c as u8)
+                (*sized_string).c_string.as_mut_ptr().add(i).write(*c)
             };
 
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
             unsafe {
-                (*sized_string).c_string.as_mut_ptr().add(i).write(c as i8)
+                (*sized_string).c_string.as_mut_ptr().add(i).write(*c as i8)
             };
         }
 
@@ -813,13 +1474,16 @@ impl Context {
         YR_VALUE { ss: sized_string }
     }
 
-    fn yr_value_from_argument(&self, arg: &Argument) -> YR_VALUE {
-        match arg {
+    fn yr_value_from_argument(&self, arg: &Argument) -> Result<YR_VALUE, YariError> {
+        Ok(match arg {
             Argument::Regexp(r, m) => self.re_from_str(r, m),
             Argument::Integer(i) => self.i_from_int(i),
-            Argument::Float(_f) => unimplemented!(),
-            Argument::String(s) => self.str_from_str(s),
-        }
+            Argument::Float(f) => self.f_from_float(f),
+            // Strings are passed in the escaped form, both literals and evaluated values
+            Argument::String(s) => self.str_from_bytes(&unescape_string(s)?),
+            // Sub-expressions have to be evaluated before the call
+            Argument::Expression(_) => return Err(YariError::EvalError),
+        })
     }
 
     /// Convert the regexp modifiers string to bitflags used by YARA.
@@ -888,20 +1552,20 @@ impl Context {
         YR_VALUE { re: re_ptr }
     }
 
-    fn collect_arguments(&self, str_args: Vec<Argument>) -> *mut YR_VALUE {
+    fn collect_arguments(&self, str_args: Vec<Argument>) -> Result<*mut YR_VALUE, YariError> {
         if str_args.is_empty() {
-            return ptr::null_mut();
+            return Ok(ptr::null_mut());
         }
 
         // Allocate argument array
         let mut args: Vec<YR_VALUE> = Vec::with_capacity(str_args.len());
 
         for arg in str_args {
-            args.push(self.yr_value_from_argument(&arg));
+            args.push(self.yr_value_from_argument(&arg)?);
         }
 
         // Convert to C array
-        args.leak().as_mut_ptr()
+        Ok(args.leak().as_mut_ptr())
     }
 
     pub fn call_function_with_args(
@@ -940,7 +1604,7 @@ impl Context {
             let arg_str = unsafe { CStr::from_ptr(prototype.arguments_fmt) };
 
             if eval_args_c_string.as_c_str() == arg_str {
-                let yr_args = self.collect_arguments(args);
+                let yr_args = self.collect_arguments(args)?;
                 let func_code = prototype.code.expect("No function assigned");
                 unsafe {
                     func_code(yr_args, &mut **self.context, func_ptr);
@@ -1018,55 +1682,60 @@ impl Context {
         unsafe { self.return_obj_if_type_ok(obj_ptr) }
     }
 
+    /// Strings of the `rule` referenced by `prefix` with their names.
+    ///
+    /// Prefix is a string name (`s00`), a name prefix with wildcard (`s*`, `*`) or an index of
+    /// the anonymous string (`[1]`). Anonymous strings are named by their index (`$[1]`).
+    /// Chained strings are represented by their first part, the same as in YARA.
+    fn get_matching_strings(
+        &self,
+        rule: YR_RULE,
+        prefix: &str,
+    ) -> Result<Vec<(String, *const YR_STRING)>, YariError> {
+        let matches_prefix = |name: &str| match prefix.strip_suffix('*') {
+            Some(prefix) => name[1..].starts_with(prefix),
+            None => &name[1..] == prefix,
+        };
+        let res: Vec<_> = unsafe { string_identifiers(&rule) }
+            .into_iter()
+            .filter(|(_, name)| matches_prefix(name))
+            .map(|(s, name)| (name, s))
+            .collect();
+
+        if res.is_empty() {
+            Err(YariError::UndeclaredStringError)
+        } else {
+            Ok(res)
+        }
+    }
+
+    /// The first string of the `rule` referenced by `prefix`.
     fn get_matching_string(
-        &mut self,
+        &self,
         rule: YR_RULE,
         prefix: &str,
     ) -> Result<*const YR_STRING, YariError> {
-        let prefix = CString::new(prefix).unwrap();
-        let strings_table = unsafe { rule.__bindgen_anon_4.strings };
-
-        for s in YrStringIterator::new(strings_table) {
-            let mut prefix_ptr = prefix.as_ptr();
+        Ok(self.get_matching_strings(rule, prefix)?[0].1)
+    }
 
-            #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-            unsafe {
-                let mut identifier = (*s).__bindgen_anon_3.identifier.offset(1);
-                while *identifier != '\0' as u8
-                    && *prefix_ptr != '\0' as u8
-                    && *identifier == *prefix_ptr
-                {
-                    identifier = identifier.offset(1);
-                    prefix_ptr = prefix_ptr.offset(1);
-                }
+    /// Matches of the `string` in the sample.
+    fn matches_of(&self, string: *const YR_STRING) -> YR_MATCHES {
+        unsafe { *self.context.matches.offset((*string).idx as isize) }
+    }
 
-                if (*identifier == '\0' as u8 && *prefix_ptr == '\0' as u8)
-                    || *prefix_ptr == '*' as u8
-                {
-                    return Ok(s);
-                }
+    /// Offsets (`base + offset`) of the matches of the `string`.
+    fn match_offsets(&self, string: *const YR_STRING) -> impl Iterator<Item = i64> {
+        let mut m = self.matches_of(string).head;
+        std::iter::from_fn(move || {
+            if m.is_null() {
+                None
+            } else {
+                let offset = unsafe { (*m).base + (*m).offset };
+                m = unsafe { (*m).next };
+                Some(offset)
             }
-
-            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
-            unsafe {
-                let mut identifier = (*s).__bindgen_anon_3.identifier.offset(1);
-                while *identifier != '\0' as i8
-                    && *prefix_ptr != '\0' as i8
-                    && *identifier == *prefix_ptr
-                {
-                    identifier = identifier.offset(1);
-                    prefix_ptr = prefix_ptr.offset(1);
-                }
-
-                if (*identifier == '\0' as i8 && *prefix_ptr == '\0' as i8)
-                    || *prefix_ptr == '*' as i8
-                {
-                    return Ok(s);
-                }
-            }
-        }
-        Err(YariError::UndeclaredStringError)
-    }
+        })
+    }
 
     pub fn get_string(
         &mut self,
@@ -1078,20 +1747,21 @@ impl Context {
         debug!("Getting the value of string {:?}", prefix);
         let mut obj = YR_OBJECT::default();
 
-        let string = self.get_matching_string(rule, prefix)?;
-        let matches = unsafe { *self.context.matches.offset((*string).idx as isize) };
+        // Offsets and lengths of a wildcard (`@s*[1]`) would mix the matches of different strings
+        if prefix.ends_with('*') && op != StrOperation::MatchesCount {
+            return Err(YariError::EvalError);
+        }
+
+        let strings = self.get_matching_strings(rule, prefix)?;
+        let matches = self.matches_of(strings[0].1);
 
         let val = match op {
             StrOperation::MatchesOnce => Ok(!matches.tail.is_null() as i64),
-            StrOperation::MatchesCount => {
-                let mut cnt = 0;
-                let mut m = matches.head;
-                while !m.is_null() {
-                    cnt += 1;
-                    m = unsafe { (*m).next };
-                }
-                Ok(cnt)
-            }
+            // Matches of all the strings are summed for the wildcard (`#s*`)
+            StrOperation::MatchesCount => Ok(strings
+                .iter()
+                .map(|(_, string)| self.match_offsets(*string).count() as i64)
+                .sum()),
             StrOperation::MatchOffset => {
                 let mut cnt = 0;
                 let mut index_found = false;
@@ -1144,6 +1814,44 @@ impl Context {
         Ok(obj)
     }
 
+    /// All the matches of the string `identifier` declared in the rule `rule_name`.
+    ///
+    /// Rule name can be qualified by a namespace (`namespace:rule`). Identifier can be written
+    /// with or without the `$` (e.g. `$s00` or `s00`). Wildcards (`$s*`) are not supported, the
+    /// matches of different strings could not be told apart.
+    pub fn string_matches(
+        &mut self,
+        rule_name: &str,
+        identifier: &str,
+    ) -> Result<Vec<StringMatch>, YariError> {
+        let rule = self
+            .get_rule_context(Some(rule_name))?
+            .1
+            .ok_or(YariError::RuleMissingError)?;
+        self.get_string_matches(rule, identifier.trim_start_matches('$'))
+    }
+
+    fn get_string_matches(
+        &mut self,
+        rule: YR_RULE,
+        prefix: &str,
+    ) -> Result<Vec<StringMatch>, YariError> {
+        if prefix.ends_with('*') {
+            return Err(YariError::UndeclaredStringError);
+        }
+
+        let string = self.get_matching_string(rule, prefix)?;
+        let matches = unsafe { *self.context.matches.offset((*string).idx as isize) };
+
+        let mut res = Vec::new();
+        let mut m = matches.head;
+        while !m.is_null() {
+            res.push(unsafe { StringMatch::from_yr_match(&*m, &*string) });
+            m = unsafe { (*m).next };
+        }
+        Ok(res)
+    }
+
     fn get_rule_context(
         &mut self,
         rule_name: Option<&str>,
@@ -1161,14 +1869,26 @@ impl Context {
             #[cfg(feature = "avast")]
             let rules_table = rules.rules_table;
 
+            // Rule name can be qualified by a namespace (`namespace:rule`)
+            let (namespace, rule_name) = match rule_name.split_once(':') {
+                Some((namespace, rule_name)) => (Some(namespace), rule_name),
+                None => (None, rule_name),
+            };
+
             let mut i = 0_isize;
             while (unsafe { *rules_table.offset(i) }).flags != RULE_FLAGS_NULL {
                 let r = unsafe { *rules_table.offset(i) };
                 let curr_rule_name = unsafe { CStr::from_ptr(r.__bindgen_anon_1.identifier) }
                     .to_str()
                     .unwrap();
+                let curr_namespace =
+                    unsafe { CStr::from_ptr((*r.__bindgen_anon_5.ns).__bindgen_anon_1.name) }
+                        .to_str()
+                        .unwrap();
 
-                if curr_rule_name == rule_name {
+                if curr_rule_name == rule_name
+                    && namespace.map_or(true, |namespace| namespace == curr_namespace)
+                {
                     rule_ctx = (Some(i), Some(r));
                     break;
                 }
@@ -1185,79 +1905,76 @@ impl Context {
         let (rule_name, expr) = parse(str_expr)?;
         debug!("Parsed expression {:?}", expr);
 
+        let rule_ctx = self.get_rule_context(rule_name)?;
+
+        self.eval_expression(rule_name, rule_ctx, expr)
+    }
+
+    /// Evaluate parsed expression tree natively, use fallback evaluation only for the parts of
+    /// the expression YARI cannot evaluate itself.
+    fn eval_expression(
+        &mut self,
+        rule_name: Option<&str>,
+        rule_ctx: (Option<isize>, Option<YR_RULE>),
+        expr: Expression,
+    ) -> Result<YrValue, YariError> {
         // Import module used in expression
         let expr_module = expr.get_module();
         if let Some(module) = expr_module {
             self.import_module(module)?;
         }
 
-        let rule_ctx = self.get_rule_context(rule_name)?;
-
         match expr {
             Expression::Function { name, args } => {
-                let obj = self.call_function_with_args(name, args)?;
-                Ok(unsafe { YrValue::from(obj) })
-            }
-            Expression::Value(name) => {
-                if !self.use_fallback_eval {
-                    // Try to evaluate expression using YARI
-                    let obj = self.get_value(name);
-                    if let Ok(obj) = obj {
-                        Ok(unsafe { YrValue::from(obj) })
-                    } else if let Some(rule_name) = rule_name {
-                        // YARI evaluation failed, try fallback evaluation
-                        let rules_text = expression_to_rules_with_condition(
-                            self.rule_string
-                                .as_ref()
-                                .ok_or(YariError::RuleMissingError)?,
-                            rule_name,
-                            name,
-                        )?;
-                        Context::new(Some(self.input.clone()), Some(rules_text), true)
-                            .eval(rule_name)
+                // Evaluate sub-expressions used as arguments before the call
+                let mut literals = Vec::with_capacity(args.len());
+                let mut values = Vec::new();
+                for arg in args {
+                    if let Argument::Expression(expr) = arg {
+                        values.push(self.eval_expression(rule_name, rule_ctx, *expr)?);
+                        literals.push(None);
                     } else {
-                        // Fallback evaluation without rule context, typically called when `name` is rule name itself
-                        Context::new(Some(self.input.clone()), self.rule_string.clone(), true)
-                            .eval(name)
-                    }
-                } else {
-                    // Evaluation using fallback scanner
-                    let mut obj = YR_OBJECT::default();
-                    let mut obj_ptr = ptr::null::<YR_OBJECT>();
-
-                    // Search for variables
-                    #[cfg(feature = "avast")]
-                    if let Some(idx) = rule_ctx.0 {
-                        obj_ptr = unsafe {
-                            let name = CString::new(name).unwrap();
-                            yr_hash_table_lookup(
-                                *(*self.fallback_scanner)
-                                    .internal_variable_tables
-                                    .offset(idx),
-                                name.as_ptr(),
-                                ptr::null(),
-                            )
-                        }
-                        .cast::<YR_OBJECT>();
+                        literals.push(Some(arg));
                     }
+                }
 
-                    if obj_ptr.is_null() {
-                        let rule_matching = self.rules_matching.contains(&name.to_string());
-                        let rule_not_matching = self.rules_not_matching.contains(&name.to_string());
+                // Function called with undefined argument is undefined, the same as in YARA
+                if values.iter().any(YrValue::is_undefined) {
+                    return Ok(YrValue::Integer(YR_UNDEFINED));
+                }
 
-                        if rule_matching || rule_not_matching {
-                            obj.type_ = OBJECT_TYPE_INTEGER as i8;
-                            obj.value.i = rule_matching as i64;
-                            obj_ptr = &obj;
-                        }
-                    }
+                // Evaluated strings are passed in the escaped form, the same as literals
+                let values: Vec<YrValue> = values.into_iter().map(YrValue::into_escaped).collect();
+                let mut values = values.iter();
+                let args = literals
+                    .into_iter()
+                    .map(|arg| match arg {
+                        Some(arg) => Ok(arg),
+                        None => Argument::try_from(values.next().ok_or(YariError::EvalError)?),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                    if !obj_ptr.is_null() {
-                        Ok(unsafe { YrValue::from(obj_ptr) })
-                    } else {
-                        Err(YariError::SymbolNotFound(name.to_string()))
-                    }
-                }
+                let obj = self.call_function_with_args(name, args)?;
+                Ok(unsafe { YrValue::from(obj) })
+            }
+            Expression::Value(name) => self.eval_value(rule_name, rule_ctx.0, name),
+            // Match status of each string for the wildcard (`$s*`)
+            Expression::String {
+                operator: StrOperation::MatchesOnce,
+                prefix,
+                ..
+            } if prefix.ends_with('*') => {
+                let strings = self
+                    .get_matching_strings(rule_ctx.1.ok_or(YariError::RuleMissingError)?, prefix)?;
+                Ok(YrValue::Dictionary(
+                    strings
+                        .into_iter()
+                        .map(|(name, string)| {
+                            let found = !self.matches_of(string).tail.is_null();
+                            (name, YrValue::Integer(found as i64))
+                        })
+                        .collect(),
+                ))
             }
             Expression::String {
                 operator,
@@ -1272,10 +1989,140 @@ impl Context {
                 )?;
                 Ok(unsafe { YrValue::from(&obj) })
             }
+            Expression::StringAt { prefix, offset } => {
+                let rule = rule_ctx.1.ok_or(YariError::RuleMissingError)?;
+                let string = self.get_matching_string(rule, prefix)?;
+                let offset = self
+                    .eval_expression(rule_name, rule_ctx, *offset)?
+                    .as_integer()?;
+
+                // String at undefined offset is false, the same as in YARA
+                let found =
+                    offset.is_some_and(|offset| self.match_offsets(string).any(|o| o == offset));
+                Ok(YrValue::Integer(found as i64))
+            }
+            Expression::StringIn {
+                operator,
+                prefix,
+                lower,
+                upper,
+            } => {
+                let rule = rule_ctx.1.ok_or(YariError::RuleMissingError)?;
+                let string = self.get_matching_string(rule, prefix)?;
+                let lower = self
+                    .eval_expression(rule_name, rule_ctx, *lower)?
+                    .as_integer()?;
+                let upper = self
+                    .eval_expression(rule_name, rule_ctx, *upper)?
+                    .as_integer()?;
+
+                let (Some(lower), Some(upper)) = (lower, upper) else {
+                    return Ok(match operator {
+                        StrOperation::MatchesCount => YrValue::Integer(YR_UNDEFINED),
+                        _ => YrValue::Integer(0),
+                    });
+                };
+
+                let count = self
+                    .match_offsets(string)
+                    .filter(|offset| (lower..=upper).contains(offset))
+                    .count() as i64;
+                Ok(YrValue::Integer(match operator {
+                    StrOperation::MatchesCount => count,
+                    _ => (count > 0) as i64,
+                }))
+            }
+            Expression::Of {
+                quantifier,
+                strings,
+            } => {
+                let rule = rule_ctx.1.ok_or(YariError::RuleMissingError)?;
+                let mut set: Vec<*const YR_STRING> = Vec::new();
+                for prefix in strings {
+                    for (_, string) in self.get_matching_strings(rule, prefix)? {
+                        if !set.contains(&string) {
+                            set.push(string);
+                        }
+                    }
+                }
+
+                let found = set
+                    .iter()
+                    .filter(|string| !self.matches_of(**string).tail.is_null())
+                    .count();
+                let res = match quantifier {
+                    Quantifier::All => found == set.len(),
+                    Quantifier::Any => found > 0,
+                    Quantifier::None => found == 0,
+                    Quantifier::Count(count) => {
+                        match self
+                            .eval_expression(rule_name, rule_ctx, *count)?
+                            .as_integer()?
+                        {
+                            Some(count) => found as i64 >= count,
+                            None => return Ok(YrValue::Integer(YR_UNDEFINED)),
+                        }
+                    }
+                    Quantifier::Percent(percent) => {
+                        match self
+                            .eval_expression(rule_name, rule_ctx, *percent)?
+                            .as_integer()?
+                        {
+                            Some(percent) => {
+                                found as f64 / set.len() as f64 * 100.0 >= percent as f64
+                            }
+                            None => return Ok(YrValue::Integer(YR_UNDEFINED)),
+                        }
+                    }
+                };
+                Ok(YrValue::Integer(res as i64))
+            }
+            Expression::StringMatches(prefix) => Ok(YrValue::Array(
+                self.get_string_matches(rule_ctx.1.ok_or(YariError::RuleMissingError)?, prefix)?
+                    .iter()
+                    .map(|m| m.into())
+                    .collect(),
+            )),
+            Expression::Integer(i) => Ok(YrValue::Integer(i)),
+            Expression::Float(f) => Ok(YrValue::Float(f)),
+            Expression::Text(text) => Ok(YrValue::from_bytes(unescape_string(text)?)),
+            Expression::Filesize => Ok(YrValue::Integer(self.block.size as i64)),
+            Expression::Entrypoint => Ok(YrValue::Integer(self.context.entry_point as i64)),
+            Expression::Unary { operator, operand } => self
+                .eval_expression(rule_name, rule_ctx, *operand)?
+                .unary_operation(operator),
+            // Boolean operators are short-circuited, the same way as YARA does it
+            Expression::Binary {
+                operator: BinaryOperator::And,
+                left,
+                right,
+            } => {
+                let res = bool::try_from(self.eval_expression(rule_name, rule_ctx, *left)?)?
+                    && bool::try_from(self.eval_expression(rule_name, rule_ctx, *right)?)?;
+                Ok(YrValue::Integer(res as i64))
+            }
+            Expression::Binary {
+                operator: BinaryOperator::Or,
+                left,
+                right,
+            } => {
+                let res = bool::try_from(self.eval_expression(rule_name, rule_ctx, *left)?)?
+                    || bool::try_from(self.eval_expression(rule_name, rule_ctx, *right)?)?;
+                Ok(YrValue::Integer(res as i64))
+            }
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.eval_expression(rule_name, rule_ctx, *left)?;
+                let right = self.eval_expression(rule_name, rule_ctx, *right)?;
+                left.binary_operation(operator, right)
+            }
             Expression::Complex(value) => {
                 debug!(
                     "Evaluating expression {:?} using fallback YARA evaluation..",
-                    str_expr
+                    value
                 );
 
                 if self.use_fallback_eval {
@@ -1288,14 +2135,8 @@ impl Context {
                 if let Some(rule_name) = rule_name {
                     // Extract expression into rule condition and call fallback evaluation.
                     // TODO: if ruleset is not present, try to generate a new rule
-                    let rules_text = expression_to_rules_with_condition(
-                        self.rule_string
-                            .as_ref()
-                            .ok_or(YariError::RuleMissingError)?,
-                        rule_name,
-                        value,
-                    )?;
-                    Context::new(Some(self.input.clone()), Some(rules_text), true).eval(rule_name)
+                    let rule_sources = self.rule_sources_with_condition(rule_name, value)?;
+                    self.fallback_context(rule_sources)?.rule_result(rule_name)
                 } else {
                     Err(YariError::RuleMissingError)
                 }
@@ -1303,6 +2144,503 @@ impl Context {
         }
     }
 
+    /// Results of the profiled scan, `None` if the context was not built with
+    /// [`ContextBuilder::with_profiling`].
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Scan the sample with profiling enabled and collect the cost of the rules and strings.
+    fn run_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+        if self.context.rules.is_null() {
+            return;
+        }
+
+        // Strings are profiled by YARI, rules by libyara
+        unsafe {
+            self.reset_matches();
+            self.scan_strings();
+        }
+        #[cfg(feature = "profiling")]
+        let rule_costs = unsafe { self.rule_costs() };
+        #[cfg(not(feature = "profiling"))]
+        let rule_costs: Option<HashMap<String, u64>> = None;
+
+        let rules = unsafe { *self.context.rules };
+
+        #[cfg(not(feature = "avast"))]
+        let rules_table = unsafe { rules.__bindgen_anon_1.rules_table };
+        #[cfg(feature = "avast")]
+        let rules_table = rules.rules_table;
+
+        let mut profile = Profile::default();
+        let mut i = 0_isize;
+        while (unsafe { *rules_table.offset(i) }).flags != RULE_FLAGS_NULL {
+            let rule = unsafe { &*rules_table.offset(i) };
+            let name = unsafe { qualified_name(rule) };
+
+            let strings: Vec<StringProfile> = unsafe { string_identifiers(rule) }
+                .into_iter()
+                .map(|(string, identifier)| {
+                    // Statistics of the chained parts belong to the whole string
+                    let stats = unsafe { chain_parts(string) }
+                        .into_iter()
+                        .filter_map(|s| self.string_stats.get(unsafe { (*s).idx } as usize))
+                        .fold(StringStats::default(), |mut acc, s| {
+                            acc.atom_matches += s.atom_matches;
+                            acc.match_time += s.match_time;
+                            acc
+                        });
+                    StringProfile {
+                        rule: name.clone(),
+                        identifier,
+                        atom_matches: stats.atom_matches,
+                        match_time: stats.match_time,
+                    }
+                })
+                .collect();
+
+            profile.rules.push(RuleProfile {
+                rule: name.clone(),
+                cost: rule_costs
+                    .as_ref()
+                    .and_then(|costs| costs.get(&name).copied()),
+                atom_matches: strings.iter().map(|s| s.atom_matches).sum(),
+                match_time: strings.iter().map(|s| s.match_time).sum(),
+            });
+            profile.strings.extend(strings);
+            i += 1;
+        }
+
+        profile.sort();
+        self.profile = Some(profile);
+    }
+
+    /// Scan the sample with the profiling scanner and return the rule costs computed by libyara.
+    ///
+    /// Available only with the `profiling` feature, libyara reports zero costs unless it was
+    /// built with `--enable-profiling`. The scanner is created once and reused for every sample.
+    #[cfg(feature = "profiling")]
+    unsafe fn rule_costs(&mut self) -> Option<HashMap<String, u64>> {
+        if self.profiling_scanner.is_null() {
+            let mut scanner: *mut YR_SCANNER = ptr::null_mut();
+            if yr_scanner_create(self.context.rules, &mut scanner) != ERROR_SUCCESS as i32 {
+                error!("Cannot create the profiling scanner");
+                return None;
+            }
+
+            if let Err(e) = self.define_scanner_externals(scanner) {
+                error!(
+                    "Cannot define external variables of the profiling scanner: {}",
+                    e
+                );
+            }
+            yr_scanner_set_timeout(scanner, 0);
+            self.profiling_scanner = scanner;
+        }
+
+        // Module data can change between the scans, so the callback data are updated every time
+        yr_scanner_set_callback(
+            self.profiling_scanner,
+            Some(default_callback),
+            &mut *self.module_data_linked_list as *mut _ as *mut c_void,
+        );
+        yr_scanner_reset_profiling_info(self.profiling_scanner);
+        yr_scanner_scan_mem_blocks(self.profiling_scanner, self.iterator.as_mut());
+
+        // The list is terminated by an entry with null rule
+        let info = yr_scanner_get_profiling_info(self.profiling_scanner);
+        if info.is_null() {
+            return None;
+        }
+
+        let mut costs = HashMap::new();
+        let mut entry = info;
+        while !(*entry).rule.is_null() {
+            costs.insert(qualified_name(&*(*entry).rule), (*entry).cost);
+            entry = entry.offset(1);
+        }
+        yr_free(info.cast::<c_void>());
+        Some(costs)
+    }
+
+    /// Atoms chosen by the compiler for the strings of the rule `rule_name`.
+    ///
+    /// Every atom is reported with its quality and the number of hits in the sample. Rule name
+    /// can be qualified by a namespace (`namespace:rule`).
+    pub fn atoms(&self, rule_name: &str) -> Result<Vec<StringAtoms>, YariError> {
+        let name = self
+            .rule(rule_name)
+            .ok_or(YariError::RuleMissingError)?
+            .qualified_name();
+        let rules = unsafe { &*self.context.rules };
+
+        #[cfg(not(feature = "avast"))]
+        let rules_table = unsafe { rules.__bindgen_anon_1.rules_table };
+        #[cfg(feature = "avast")]
+        let rules_table = rules.rules_table;
+
+        let mut i = 0_isize;
+        let rule = loop {
+            let rule = unsafe { &*rules_table.offset(i) };
+            if rule.flags == RULE_FLAGS_NULL {
+                return Err(YariError::RuleMissingError);
+            }
+            if unsafe { qualified_name(rule) } == name {
+                break rule;
+            }
+            i += 1;
+        };
+
+        let automaton_atoms = unsafe { automaton_atoms(rules) };
+
+        // Count the atom hits the same way the strings are scanned
+        let mut hits: HashMap<*const YR_AC_MATCH, u64> = HashMap::new();
+        let data = self.block.context as *const u8;
+        if !data.is_null() {
+            unsafe {
+                ac_scan(rules, data, self.block.size, |_, m| {
+                    *hits.entry(m).or_default() += 1;
+                    ERROR_SUCCESS as i32
+                })
+            };
+        }
+
+        let mut res = Vec::new();
+        for (string, identifier) in unsafe { string_identifiers(rule) } {
+            let parts = unsafe { chain_parts(string) };
+            let mut atoms: Vec<Atom> = automaton_atoms
+                .iter()
+                .filter(|(m, _)| {
+                    parts.contains(&unsafe { (*(**m)).__bindgen_anon_1.string }.cast_const())
+                })
+                .map(|(m, bytes)| Atom {
+                    bytes: bytes.clone(),
+                    backtrack: unsafe { (**m).backtrack },
+                    quality: self.atom_quality(bytes),
+                    hits: hits.get(m).copied().unwrap_or(0),
+                })
+                .collect();
+            atoms.sort_by(|a, b| a.backtrack.cmp(&b.backtrack).then(a.bytes.cmp(&b.bytes)));
+
+            res.push(StringAtoms { identifier, atoms });
+        }
+        Ok(res)
+    }
+
+    /// Quality of the atom `bytes` as computed by the compiler.
+    fn atom_quality(&self, bytes: &[u8]) -> i32 {
+        let mut atom = YR_ATOM {
+            length: bytes.len().min(4) as u8,
+            bytes: [0; 4],
+            mask: [0xff; 4],
+        };
+        atom.bytes[..atom.length as usize].copy_from_slice(&bytes[..atom.length as usize]);
+        unsafe { yr_atoms_heuristic_quality(&mut (*self.compiler).atoms_config, &mut atom) }
+    }
+
+    /// Disassemble the condition bytecode of the rule `rule_name`.
+    ///
+    /// Instructions are returned from the rule initialization up to the rule match. Rule name
+    /// can be qualified by a namespace (`namespace:rule`).
+    pub fn disassemble(&self, rule_name: &str) -> Result<Vec<Instruction>, YariError> {
+        let rule = self.rule(rule_name).ok_or(YariError::RuleMissingError)?;
+        let rules = unsafe { *self.context.rules };
+
+        #[cfg(not(feature = "avast"))]
+        let rules_table = unsafe { rules.__bindgen_anon_1.rules_table };
+        #[cfg(feature = "avast")]
+        let rules_table = rules.rules_table;
+
+        let mut symbols = Symbols::default();
+        let mut i = 0_isize;
+        while (unsafe { *rules_table.offset(i) }).flags != RULE_FLAGS_NULL {
+            unsafe { symbols.add_rule(&*rules_table.offset(i)) };
+            i += 1;
+        }
+
+        let instructions = unsafe { disasm::disassemble(rules.code_start, &symbols)? };
+        disasm::rule_instructions(instructions, &rule.qualified_name())
+    }
+
+    /// Explain the result of the rule `rule_name`.
+    ///
+    /// The condition of the rule is parsed and every sub-expression is evaluated, operands
+    /// skipped by the short-circuit evaluation are marked. Rule name can be qualified by a
+    /// namespace (`namespace:rule`). Conditions which cannot be parsed are evaluated as a whole.
+    pub fn explain(&mut self, rule_name: &str) -> Result<Explanation, YariError> {
+        if self.compiled_rules {
+            return Err(YariError::RuleSourceMissingError);
+        }
+
+        let rule_ctx = self.get_rule_context(Some(rule_name))?;
+        if rule_ctx.1.is_none() {
+            return Err(YariError::RuleMissingError);
+        }
+
+        let (namespace, name) = match rule_name.split_once(':') {
+            Some((namespace, name)) => (Some(namespace), name),
+            None => (None, rule_name),
+        };
+        let condition = self
+            .rule_sources
+            .iter()
+            .filter(|source| namespace.map_or(true, |ns| ns == source.namespace_name()))
+            .find_map(|source| rule_condition(&source.expanded_source(), name).map(str::to_string))
+            .ok_or(YariError::RuleMissingError)?;
+
+        let input = format!("{}|{}", rule_name, condition);
+        let (rule_name, expr) = parse(&input)?;
+        Ok(self.explain_expression(rule_name, rule_ctx, expr))
+    }
+
+    fn explain_expression(
+        &mut self,
+        rule_name: Option<&str>,
+        rule_ctx: (Option<isize>, Option<YR_RULE>),
+        expr: Expression,
+    ) -> Explanation {
+        let expression = expr.to_string();
+        let explained = |outcome, children| Explanation {
+            expression: expression.clone(),
+            outcome,
+            children,
+        };
+
+        match expr {
+            Expression::Binary {
+                operator: operator @ (BinaryOperator::And | BinaryOperator::Or),
+                left,
+                right,
+            } => {
+                let left = self.explain_expression(rule_name, rule_ctx, *left);
+                let left_value = match left.value().cloned().map(bool::try_from) {
+                    Some(Ok(value)) => value,
+                    _ => {
+                        let right = self.explain_expression(rule_name, rule_ctx, *right);
+                        let error = Outcome::Error("cannot evaluate the operand".to_string());
+                        return explained(error, vec![left, right]);
+                    }
+                };
+
+                // The result is decided by the left operand
+                if left_value == (operator == BinaryOperator::Or) {
+                    let right = Explanation {
+                        expression: right.to_string(),
+                        outcome: Outcome::ShortCircuited,
+                        children: Vec::new(),
+                    };
+                    let value = YrValue::Integer(left_value as i64);
+                    return explained(Outcome::Value(value), vec![left, right]);
+                }
+
+                let right = self.explain_expression(rule_name, rule_ctx, *right);
+                let outcome = match right.value().cloned().map(bool::try_from) {
+                    Some(Ok(value)) => Outcome::Value(YrValue::Integer(value as i64)),
+                    Some(Err(e)) => Outcome::Error(e.to_string()),
+                    None => Outcome::Error("cannot evaluate the operand".to_string()),
+                };
+                explained(outcome, vec![left, right])
+            }
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.explain_expression(rule_name, rule_ctx, *left);
+                let right = self.explain_expression(rule_name, rule_ctx, *right);
+                let outcome = match (left.value(), right.value()) {
+                    (Some(l), Some(r)) => match l.clone().binary_operation(operator, r.clone()) {
+                        Ok(value) => Outcome::Value(value),
+                        Err(e) => Outcome::Error(e.to_string()),
+                    },
+                    _ => Outcome::Error("cannot evaluate the operand".to_string()),
+                };
+                explained(outcome, vec![left, right])
+            }
+            Expression::Unary { operator, operand } => {
+                let operand = self.explain_expression(rule_name, rule_ctx, *operand);
+                let outcome = match operand.value() {
+                    Some(value) => match value.clone().unary_operation(operator) {
+                        Ok(value) => Outcome::Value(value),
+                        Err(e) => Outcome::Error(e.to_string()),
+                    },
+                    None => Outcome::Error("cannot evaluate the operand".to_string()),
+                };
+                explained(outcome, vec![operand])
+            }
+            expr => {
+                let outcome = match self.eval_expression(rule_name, rule_ctx, expr) {
+                    Ok(value) => Outcome::Value(value),
+                    Err(e) => Outcome::Error(e.to_string()),
+                };
+                explained(outcome, Vec::new())
+            }
+        }
+    }
+
+    /// Evaluate value identified by `name`.
+    ///
+    /// Module values are read directly from the objects cache. Other identifiers (rule names,
+    /// rule variables) are evaluated using the fallback scanner.
+    fn eval_value(
+        &mut self,
+        rule_name: Option<&str>,
+        #[allow(unused_variables)] rule_idx: Option<isize>,
+        name: &str,
+    ) -> Result<YrValue, YariError> {
+        if !self.use_fallback_eval {
+            // Try to evaluate expression using YARI
+            let obj = self.get_value(name);
+            if let Ok(obj) = obj {
+                Ok(unsafe { YrValue::from(obj) })
+            } else if let Some(external) = self.externals.get(name) {
+                Ok(external.into())
+            } else if let Some(rule_name) = rule_name {
+                // YARI evaluation failed, try fallback evaluation
+                let rule_sources = self.rule_sources_with_condition(rule_name, name)?;
+                self.fallback_context(rule_sources)?.rule_result(rule_name)
+            } else {
+                // Fallback evaluation without rule context, typically called when `name` is rule name itself
+                if self.compiled_rules {
+                    self.scan_compiled_rules()?;
+                    self.rule_result(name)
+                } else {
+                    self.fallback_context(self.rule_sources.clone())?
+                        .rule_result(name)
+                }
+            }
+        } else {
+            // Evaluation using fallback scanner
+            #[allow(unused_mut)]
+            let mut obj_ptr = ptr::null::<YR_OBJECT>();
+
+            // Search for variables
+            #[cfg(feature = "avast")]
+            if let Some(idx) = rule_idx {
+                obj_ptr = unsafe {
+                    let name = CString::new(name).unwrap();
+                    yr_hash_table_lookup(
+                        *(*self.fallback_scanner)
+                            .internal_variable_tables
+                            .offset(idx),
+                        name.as_ptr(),
+                        ptr::null(),
+                    )
+                }
+                .cast::<YR_OBJECT>();
+            }
+
+            if !obj_ptr.is_null() {
+                Ok(unsafe { YrValue::from(obj_ptr) })
+            } else {
+                self.rule_result(name)
+            }
+        }
+    }
+
+    /// Create context for the fallback evaluation of `rule_sources`.
+    fn fallback_context(&self, rule_sources: Vec<RuleSource>) -> Result<Context, YariError> {
+        Context::new(
+            Some(self.sample.clone()),
+            rule_sources,
+            self.externals.clone(),
+            true,
+        )
+    }
+
+    /// Copy of the rule sources with condition of `rule_name` replaced by `expression`.
+    ///
+    /// Rule name can be qualified by a namespace (`namespace:rule`), otherwise the first source
+    /// with the rule is used.
+    fn rule_sources_with_condition(
+        &self,
+        rule_name: &str,
+        expression: &str,
+    ) -> Result<Vec<RuleSource>, YariError> {
+        if self.compiled_rules {
+            return Err(YariError::RuleSourceMissingError);
+        }
+
+        let (namespace, rule_name) = match rule_name.split_once(':') {
+            Some((namespace, rule_name)) => (Some(namespace), rule_name),
+            None => (None, rule_name),
+        };
+
+        // The rule can be defined in an included file, so it is rewritten in the expanded source
+        let mut rule_sources = self.rule_sources.clone();
+        let source = rule_sources
+            .iter_mut()
+            .filter(|source| namespace.map_or(true, |ns| ns == source.namespace_name()))
+            .find_map(|source| {
+                let expanded = source.expanded_source();
+                rules_contain_rule(&expanded, rule_name).then(|| {
+                    source.source = expanded;
+                    source
+                })
+            })
+            .ok_or(YariError::RuleMissingError)?;
+        source.source = expression_to_rules_with_condition(&source.source, rule_name, expression)?;
+
+        Ok(rule_sources)
+    }
+
+    /// Evaluate loaded compiled rules with the fallback scanner.
+    ///
+    /// Compiled rules cannot be recompiled into a fallback context, so the fallback scanner
+    /// shares the rules of this context. Matching rules are collected into this context.
+    fn scan_compiled_rules(&mut self) -> Result<(), YariError> {
+        self.rules_matching.clear();
+        self.rules_not_matching.clear();
+
+        unsafe {
+            if self.fallback_scanner.is_null() {
+                yr_scanner_create(
+                    self.context.rules,
+                    &mut self.fallback_scanner as *mut *mut YR_SCANNER,
+                );
+                if let Err(e) = self.define_scanner_externals(self.fallback_scanner) {
+                    yr_scanner_destroy(self.fallback_scanner);
+                    self.fallback_scanner = ptr::null_mut();
+                    return Err(e);
+                }
+                yr_scanner_set_timeout(self.fallback_scanner, 0);
+                yr_scanner_set_flags(self.fallback_scanner, 8 | 16); // SCAN_FLAGS_REPORT_RULES_MATCHING | SCAN_FLAGS_REPORT_RULES_NOT_MATCHING
+            }
+
+            // Context can move between the scans, so the callback data are updated every time
+            yr_scanner_set_callback(
+                self.fallback_scanner,
+                Some(rule_match_callback),
+                (self as *mut Context).cast::<c_void>(),
+            );
+            yr_scanner_scan_mem_blocks(self.fallback_scanner, self.iterator.as_mut());
+        }
+        Ok(())
+    }
+
+    /// Result of the rule `name` evaluated by the fallback scanner.
+    ///
+    /// Rule name can be qualified by a namespace (`namespace:rule`).
+    fn rule_result(&self, name: &str) -> Result<YrValue, YariError> {
+        let is_rule = |qualified_name: &String| {
+            qualified_name == name
+                || (!name.contains(':')
+                    && qualified_name
+                        .split_once(':')
+                        .is_some_and(|(_, rule_name)| rule_name == name))
+        };
+
+        if self.rules_matching.iter().any(is_rule) {
+            Ok(YrValue::Integer(1))
+        } else if self.rules_not_matching.iter().any(is_rule) {
+            Ok(YrValue::Integer(0))
+        } else {
+            Err(YariError::SymbolNotFound(name.to_string()))
+        }
+    }
+
     fn filemap<P: AsRef<Path>>(&mut self, filename: P) -> YR_MAPPED_FILE {
         let filename_string = CString::new(filename.as_ref().to_str().expect("Invalid file name"))
             .expect("Invalid file name");
@@ -1317,6 +2655,95 @@ impl Context {
         }
     }
 
+    /// Get the sample data, files are mapped into the memory.
+    ///
+    /// In-memory samples are owned by the context, so the buffer stays valid until the sample is
+    /// replaced.
+    fn map_sample(&mut self) -> Result<(*const u8, usize), YariError> {
+        if let Sample::File(path) = &self.sample {
+            self.sample_mapped_file = Some(map_file(path)?);
+        }
+        Ok(self.sample_data())
+    }
+
+    /// Data of the mapped file or the in-memory sample.
+    fn sample_data(&self) -> (*const u8, usize) {
+        match (&self.sample, &self.sample_mapped_file) {
+            (Sample::Bytes(bytes), _) => (bytes.as_ptr(), bytes.len()),
+            (Sample::File(_), Some(mfile)) => (mfile.data, mfile.size),
+            (Sample::File(_), None) => (ptr::null(), 0),
+        }
+    }
+
+    fn unmap_sample(&mut self) {
+        if let Some(mut mfile) = self.sample_mapped_file.take() {
+            unsafe { yr_filemap_unmap(&mut mfile) };
+        }
+    }
+
+    /// Scan the sample for string matches.
+    ///
+    /// Only the string matching is done, conditions are evaluated on demand.
+    unsafe fn scan_strings(&mut self) {
+        let first_func = (*self.context.iterator).first.unwrap();
+        let block = first_func(self.context.iterator);
+
+        let fetch_data_func = (*block).fetch_data.expect("msg");
+        let data = fetch_data_func(block);
+
+        let mut max_match_data = 0_usize;
+        yr_get_configuration(
+            2, // YR_CONFIG_MAX_MATCH_DATA
+            (&mut max_match_data as *mut usize).cast::<c_void>(),
+        );
+        yr_notebook_create(
+            1024 * (size_of::<YR_MATCH>() + max_match_data),
+            &mut self.context.matches_notebook,
+        );
+
+        if self.profile.is_some() {
+            let mut string_stats =
+                vec![StringStats::default(); (*self.context.rules).num_strings as usize];
+            scan_mem_block(&mut **self.context, data, block, Some(&mut string_stats));
+            self.string_stats = string_stats;
+        } else {
+            _yr_scanner_scan_mem_block(&mut **self.context, data, block);
+        }
+    }
+
+    /// Forget all the matches found in the previous sample.
+    unsafe fn reset_matches(&mut self) {
+        let rules = *self.context.rules;
+
+        ptr::write_bytes(
+            self.context.rule_matches_flags,
+            0,
+            YR_BITMASK_SIZE!(rules.num_rules) as usize,
+        );
+        ptr::write_bytes(
+            self.context.ns_unsatisfied_flags,
+            0,
+            YR_BITMASK_SIZE!(rules.num_namespaces) as usize,
+        );
+        ptr::write_bytes(
+            self.context.strings_temp_disabled,
+            0,
+            YR_BITMASK_SIZE!(rules.num_strings) as usize,
+        );
+        ptr::write_bytes(self.context.matches, 0, rules.num_strings as usize);
+        ptr::write_bytes(
+            self.context.unconfirmed_matches,
+            0,
+            rules.num_strings as usize,
+        );
+
+        // Matches are allocated from the notebook, new one is created for the next scan
+        if !self.context.matches_notebook.is_null() {
+            yr_notebook_destroy(self.context.matches_notebook);
+            self.context.matches_notebook = ptr::null_mut();
+        }
+    }
+
     fn iterator_init(&mut self, buffer: *const u8, buffer_size: usize) {
         self.block.size = buffer_size;
         self.block.base = 0;
@@ -1329,15 +2756,50 @@ impl Context {
         self.iterator.file_size = Some(_yr_get_file_size);
     }
 
-    unsafe fn compile_string(&mut self, rule_cstr: &CString) -> Result<(), YariError> {
-        if yr_compiler_add_string(self.compiler, rule_cstr.as_ptr(), ptr::null())
-            != ERROR_SUCCESS as i32
-        {
-            return Err(YariError::ParserError);
+    unsafe fn compile_sources(&mut self) -> Result<(), YariError> {
+        for source in &self.rule_sources {
+            // libyara takes the rules as C strings, they would be silently cut at the NUL
+            let contains_nul = || {
+                let name = match &source.path {
+                    Some(path) => format!("{:?}", path),
+                    None => format!("of the namespace {:?}", source.namespace_name()),
+                };
+                YariError::ContextBuilderError(format!("rules {} contain NUL", name))
+            };
+            let rule_cstr = CString::new(source.source.as_str()).map_err(|_| contains_nul())?;
+            let namespace = source
+                .namespace
+                .as_ref()
+                .map(|ns| CString::new(ns.as_str()).map_err(|_| contains_nul()))
+                .transpose()?;
+
+            self.compiler_state.current_file = source.path.clone();
+            let errors = yr_compiler_add_string(
+                self.compiler,
+                rule_cstr.as_ptr(),
+                namespace.as_ref().map_or(ptr::null(), |ns| ns.as_ptr()),
+            );
+            self.compiler_state.current_file = None;
+
+            if errors != 0 {
+                // Number of errors is returned, details are collected by the compiler callback
+                return Err(YariError::CompileError(
+                    self.diagnostics()
+                        .iter()
+                        .filter(|d| d.is_error())
+                        .cloned()
+                        .collect(),
+                ));
+            }
         }
 
-        if yr_compiler_get_rules(self.compiler, &mut self.context.rules) != ERROR_SUCCESS as i32 {
-            return Err(YariError::Unknown);
+        let res = yr_compiler_get_rules(self.compiler, &mut self.context.rules);
+        if res != ERROR_SUCCESS as i32 {
+            self.context.rules = ptr::null_mut();
+            return Err(YariError::ContextBuilderError(format!(
+                "cannot get the compiled rules (error {})",
+                res
+            )));
         }
         Ok(())
     }
@@ -1369,128 +2831,99 @@ impl Context {
         ) as *mut YR_MATCHES;
     }
 
-    pub fn dump_module(&mut self, module: Module) -> Result<(), YariError> {
+    /// Structure of the `module` with the values for the current sample.
+    pub fn dump_module(&mut self, module: Module) -> Result<ModuleNode, YariError> {
         debug!("Dumping module {:?}", module);
         self.import_module(module)?;
         match self.modules.get(&module) {
-            Some(module) => {
-                self.visit_structure(module.cast::<YR_OBJECT>(), 0);
-            }
-            None => error!("Module '{}' not found", module),
+            Some(module) => Ok(self.visit_structure(module.cast::<YR_OBJECT>())),
+            None => Err(YariError::UnknownModule(module.to_string())),
         }
+    }
 
-        Ok(())
+    /// Declarations of all the symbols of the `module`.
+    ///
+    /// Unlike `dump_module` the schema does not depend on the sample, items of arrays and
+    /// dictionaries are described even if the module did not set any.
+    pub fn module_schema(&mut self, module: Module) -> Result<Schema, YariError> {
+        debug!("Walking schema of module {:?}", module);
+        self.import_module(module)?;
+        self.modules
+            .get(&module)
+            .and_then(|module| unsafe { Schema::from_object(module.cast::<YR_OBJECT>()) })
+            .ok_or_else(|| YariError::UnknownModule(module.to_string()))
     }
 
-    fn visit_structure(&self, structure_ptr: *const YR_OBJECT, depth: usize) {
+    fn visit_structure(&self, structure_ptr: *const YR_OBJECT) -> ModuleNode {
         let identifier = unsafe { CStr::from_ptr((*structure_ptr).identifier) };
-        self.visit_structure_with_name(identifier, structure_ptr, depth)
+        self.visit_structure_with_name(&identifier.to_string_lossy(), structure_ptr)
     }
 
-    fn visit_structure_with_name(
-        &self,
-        identifier: &CStr,
-        structure_ptr: *const YR_OBJECT,
-        depth: usize,
-    ) {
+    fn visit_structure_with_name(&self, name: &str, structure_ptr: *const YR_OBJECT) -> ModuleNode {
         let structure = unsafe { *structure_ptr };
+        let Some(kind) = ObjectKind::from_object_type(structure.type_) else {
+            error!("Object {:?} has unknown type {}", name, structure.type_);
+            return ModuleNode::new(name.to_string(), ObjectKind::Structure);
+        };
+        let mut node = ModuleNode::new(name.to_string(), kind);
 
-        match structure.type_ as u32 {
-            OBJECT_TYPE_INTEGER => {
-                let value = unsafe { structure.value.i };
-
-                if value == YR_UNDEFINED {
-                    println!("{}{:?} = YR_UNDEFINED", "\t".repeat(depth), identifier,);
-                } else {
-                    println!("{}{:?} = {:#x}", "\t".repeat(depth), identifier, value);
+        match kind {
+            ObjectKind::Integer | ObjectKind::Float | ObjectKind::String => {
+                let value = unsafe { YrValue::from(structure_ptr) };
+                if !value.is_undefined() {
+                    node.value = Some(value);
                 }
             }
-            OBJECT_TYPE_FUNCTION => {
-                let function = unsafe {
-                    let function_ptr: *mut YR_OBJECT_FUNCTION = structure_ptr as *mut _;
+            ObjectKind::Function => {
+                let function = unsafe { *(structure_ptr as *mut YR_OBJECT_FUNCTION) };
+                node.return_kind = unsafe { function.return_obj.as_ref() }
+                    .and_then(|ret| ObjectKind::from_object_type(ret.type_));
 
-                    *function_ptr
-                };
-
-                let ret = unsafe { object_type_to_string((*function.return_obj).type_) };
-
-                for i in 0..YR_MAX_OVERLOADED_FUNCTIONS {
-                    let prototype = function.prototypes[i as usize];
+                for prototype in function.prototypes.iter() {
                     if prototype.arguments_fmt.is_null() {
                         break;
                     }
                     let arguments = unsafe { CStr::from_ptr(prototype.arguments_fmt) };
-                    println!(
-                        "{}{:?}({:#?}) -> {}",
-                        "\t".repeat(depth),
-                        identifier,
-                        arguments,
-                        ret
-                    );
+                    node.prototypes
+                        .push(arguments.to_string_lossy().into_owned());
                 }
             }
-            OBJECT_TYPE_ARRAY => {
-                println!("{}[A] {:?}", "\t".repeat(depth), identifier);
+            ObjectKind::Array => {
                 let mut arr = unsafe { *(structure_ptr as *mut YR_OBJECT_ARRAY) };
                 for (i, s) in arr.members().enumerate() {
-                    let key = CString::new(format!("[{}]", i)).unwrap();
-                    self.visit_structure_with_name(&key, s, depth + 1);
+                    node.children
+                        .push(self.visit_structure_with_name(&format!("[{}]", i), s));
                 }
             }
-            OBJECT_TYPE_DICTIONARY => {
-                println!("{}[D] {:?}", "\t".repeat(depth), identifier);
+            ObjectKind::Dictionary => {
                 for (key, object) in YR_DICT_ITERATOR::new(structure_ptr as *mut YR_OBJECT) {
                     let key = unsafe { CStr::from_ptr((*key).c_string.as_ptr()) };
-                    self.visit_structure_with_name(key, object, depth + 1);
+                    node.children
+                        .push(self.visit_structure_with_name(&key.to_string_lossy(), object));
                 }
             }
-            OBJECT_TYPE_STRUCTURE => {
-                let structure = structure_ptr as *mut YR_OBJECT_STRUCTURE;
-                let structure = unsafe { *structure };
-
-                println!("{}[S] {:?}", "\t".repeat(depth), identifier);
-                for s in structure.members() {
-                    self.visit_structure(s, depth + 1);
+            ObjectKind::Structure => {
+                let structure = unsafe { *(structure_ptr as *mut YR_OBJECT_STRUCTURE) };
+                for s in structure.declared_members() {
+                    node.children.push(self.visit_structure(s));
                 }
             }
-            OBJECT_TYPE_STRING => {
-                let string = unsafe { YrValue::from(structure_ptr) };
-                let value = if let YrValue::String(Some(s)) = string {
-                    s
-                } else if let YrValue::String(None) = string {
-                    "NULL".to_string()
-                } else {
-                    panic!("Could not extract string from object")
-                };
-                println!("{}[STR] {:?} = {}", "\t".repeat(depth), identifier, value);
-            }
-            #[cfg(feature = "avast")]
-            OBJECT_TYPE_REFERENCE => {
-                let structure = unsafe { *(structure_ptr as *mut YR_OBJECT_REFERENCE) };
-
-                let mut target_obj_identifier = "NULL";
-                if !structure.target_obj.is_null() {
-                    let target_obj = unsafe { *structure.target_obj };
-                    target_obj_identifier =
-                        unsafe { CStr::from_ptr(target_obj.identifier).to_str().unwrap() };
+            ObjectKind::Reference => {
+                #[cfg(feature = "avast")]
+                {
+                    let structure = unsafe { *(structure_ptr as *mut YR_OBJECT_REFERENCE) };
+                    if !structure.target_obj.is_null() {
+                        let target_obj = unsafe { *structure.target_obj };
+                        node.reference = Some(
+                            unsafe { CStr::from_ptr(target_obj.identifier) }
+                                .to_string_lossy()
+                                .into_owned(),
+                        );
+                    }
                 }
-
-                println!(
-                    "{}[REF] {:?} reference to {}",
-                    "\t".repeat(depth),
-                    identifier,
-                    target_obj_identifier
-                );
-            }
-            _ => {
-                println!(
-                    "{}[U] {:?} with type '{}'",
-                    "\t".repeat(depth),
-                    identifier,
-                    object_type_to_string(structure.type_)
-                );
             }
         }
+        node
     }
 }
 
@@ -1514,6 +2947,7 @@ impl Drop for Context {
         for file in self.yr_mapped_files.iter_mut() {
             unsafe { yr_filemap_unmap(file) };
         }
+        self.unmap_sample();
         debug!("Freed filemaps");
 
         unsafe { yr_compiler_destroy(self.compiler) };
@@ -1524,6 +2958,12 @@ impl Drop for Context {
             debug!("Destroyed fallback scanner");
         }
 
+        #[cfg(feature = "profiling")]
+        if !self.profiling_scanner.is_null() {
+            unsafe { yr_scanner_destroy(self.profiling_scanner) };
+            debug!("Destroyed profiling scanner");
+        }
+
         #[cfg(not(target_os = "windows"))]
         unsafe {
             yr_scanner_destroy(&mut **self.context)
@@ -1535,7 +2975,10 @@ impl Drop for Context {
             debug!("Destroyed rules");
         }
 
-        unsafe { yr_finalize() };
+        {
+            let _guard = YR_INIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            unsafe { yr_finalize() };
+        }
 
         debug!("DONE dropping context");
     }
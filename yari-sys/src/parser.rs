@@ -2,31 +2,40 @@ use crate::error::YariError;
 use crate::module::Module;
 use nom::branch::alt;
 use nom::bytes::complete::is_not;
+use nom::bytes::complete::tag;
 use nom::bytes::complete::take_while;
 use nom::bytes::complete::take_while1;
 use nom::character::complete::alpha0;
 use nom::character::complete::anychar;
 use nom::character::complete::char;
 use nom::character::complete::digit1;
+use nom::character::complete::hex_digit1;
+use nom::character::complete::oct_digit1;
+use nom::character::complete::satisfy;
 use nom::character::is_alphabetic;
 use nom::character::is_digit;
 use nom::combinator::cut;
 use nom::combinator::map;
 use nom::combinator::map_res;
+use nom::combinator::not;
 use nom::combinator::opt;
+use nom::combinator::peek;
 use nom::combinator::recognize;
+use nom::combinator::value;
 use nom::combinator::verify;
 use nom::error::{context, ErrorKind};
 use nom::multi::many0;
 use nom::multi::separated_list0;
-use nom::number::complete::double;
+use nom::multi::separated_list1;
 use nom::sequence::delimited;
 use nom::sequence::pair;
 use nom::sequence::preceded;
+use nom::sequence::separated_pair;
 use nom::sequence::terminated;
 use nom::sequence::tuple;
 use nom::Err;
 use nom::IResult;
+use std::fmt;
 use std::str::FromStr;
 
 /// YARA function argument
@@ -36,6 +45,8 @@ pub enum Argument<'a> {
     Regexp(&'a str, &'a str),
     Float(f64),
     Integer(i64),
+    /// Sub-expression which has to be evaluated before the function is called.
+    Expression(Box<Expression<'a>>),
 }
 
 impl Argument<'_> {
@@ -45,6 +56,7 @@ impl Argument<'_> {
             Argument::Regexp(_, _) => "r",
             Argument::Integer(_) => "i",
             Argument::Float(_) => "f",
+            Argument::Expression(_) => "?",
         }
     }
 
@@ -54,12 +66,13 @@ impl Argument<'_> {
             Argument::Regexp(_, _) => 'r',
             Argument::Integer(_) => 'i',
             Argument::Float(_) => 'f',
+            Argument::Expression(_) => '?',
         }
     }
 }
 
 /// YARA string operation
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 #[repr(u8)]
 pub enum StrOperation {
     MatchesOnce,
@@ -82,6 +95,57 @@ impl TryFrom<char> for StrOperation {
     }
 }
 
+/// Quantifier of the string set in the `of` expression.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub enum Quantifier<'a> {
+    All,
+    Any,
+    None,
+    /// At least the given number of strings (`2 of them`)
+    Count(Box<Expression<'a>>),
+    /// At least the given percentage of strings (`50% of them`)
+    Percent(Box<Expression<'a>>),
+}
+
+/// YARA unary operator
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum UnaryOperator {
+    Not,
+    Defined,
+    Minus,
+    BitwiseNot,
+}
+
+/// YARA binary operator
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Contains,
+    IContains,
+    StartsWith,
+    IStartsWith,
+    EndsWith,
+    IEndsWith,
+    IEquals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseAnd,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
 /// Yara expression enum.
 #[derive(Debug, PartialEq, PartialOrd)]
 pub enum Expression<'a> {
@@ -90,11 +154,48 @@ pub enum Expression<'a> {
         args: Vec<Argument<'a>>,
     },
     Value(&'a str),
+    /// String operation, `prefix` is the string identifier as written after the operator: a name
+    /// (`s00`), a name prefix with wildcard (`s*`, `*`) or an anonymous string index (`[1]`).
     String {
         operator: StrOperation,
         prefix: &'a str,
         index: Option<i64>,
     },
+    /// String found at the offset (`$s00 at 100`).
+    StringAt {
+        prefix: &'a str,
+        offset: Box<Expression<'a>>,
+    },
+    /// String found in the range (`$s00 in (0..100)`) or number of matches in the range
+    /// (`#s00 in (0..100)`).
+    StringIn {
+        operator: StrOperation,
+        prefix: &'a str,
+        lower: Box<Expression<'a>>,
+        upper: Box<Expression<'a>>,
+    },
+    /// Quantified string set (`any of ($s*)`), `them` is represented by the `*` wildcard.
+    Of {
+        quantifier: Quantifier<'a>,
+        strings: Vec<&'a str>,
+    },
+    /// Listing of all the matches of a string (`matches($s00)`).
+    StringMatches(&'a str),
+    Integer(i64),
+    Float(f64),
+    /// String literal as written in the expression (escape sequences are not processed).
+    Text(&'a str),
+    Filesize,
+    Entrypoint,
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expression<'a>>,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression<'a>>,
+        right: Box<Expression<'a>>,
+    },
     Complex(&'a str),
 }
 
@@ -113,8 +214,7 @@ impl Expression<'_> {
         let name = match *self {
             Expression::Function { name, .. } => name,
             Expression::Value(name) => name,
-            Expression::String { .. } => return None,
-            Expression::Complex(_) => return None,
+            _ => return None,
         };
 
         name.split('.')
@@ -123,6 +223,227 @@ impl Expression<'_> {
     }
 }
 
+impl fmt::Display for StrOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            StrOperation::MatchesOnce => '$',
+            StrOperation::MatchesCount => '#',
+            StrOperation::MatchOffset => '@',
+            StrOperation::MatchLength => '!',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            UnaryOperator::Not => "not ",
+            UnaryOperator::Defined => "defined ",
+            UnaryOperator::Minus => "-",
+            UnaryOperator::BitwiseNot => "~",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl BinaryOperator {
+    /// Precedence of the operator, higher binds tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Contains
+            | BinaryOperator::IContains
+            | BinaryOperator::StartsWith
+            | BinaryOperator::IStartsWith
+            | BinaryOperator::EndsWith
+            | BinaryOperator::IEndsWith
+            | BinaryOperator::IEquals => 3,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => 4,
+            BinaryOperator::BitwiseOr => 5,
+            BinaryOperator::BitwiseXor => 6,
+            BinaryOperator::BitwiseAnd => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Sub => 9,
+            BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Mod => 10,
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BinaryOperator::Or => "or",
+            BinaryOperator::And => "and",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Contains => "contains",
+            BinaryOperator::IContains => "icontains",
+            BinaryOperator::StartsWith => "startswith",
+            BinaryOperator::IStartsWith => "istartswith",
+            BinaryOperator::EndsWith => "endswith",
+            BinaryOperator::IEndsWith => "iendswith",
+            BinaryOperator::IEquals => "iequals",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::BitwiseOr => "|",
+            BinaryOperator::BitwiseXor => "^",
+            BinaryOperator::BitwiseAnd => "&",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "\\",
+            BinaryOperator::Mod => "%",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl fmt::Display for Argument<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Argument::String(s) => write!(f, "\"{}\"", s),
+            Argument::Regexp(re, modifiers) => write!(f, "/{}/{}", re, modifiers),
+            Argument::Float(v) => write!(f, "{:?}", v),
+            Argument::Integer(i) => write!(f, "{}", i),
+            Argument::Expression(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl fmt::Display for Quantifier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantifier::All => write!(f, "all"),
+            Quantifier::Any => write!(f, "any"),
+            Quantifier::None => write!(f, "none"),
+            Quantifier::Count(e) => write!(f, "{}", Operand(e, true)),
+            Quantifier::Percent(e) => write!(f, "{}%", Operand(e, true)),
+        }
+    }
+}
+
+impl Expression<'_> {
+    /// Precedence of the binary expression, other expressions bind the tightest.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Binary { operator, .. } => operator.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+/// Operand of an operator, parenthesized if it is a binary expression and `parenthesize` is set.
+struct Operand<'a, 'b>(&'b Expression<'a>, bool);
+
+impl fmt::Display for Operand<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expression::Binary { .. } if self.1 => write!(f, "({})", self.0),
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl fmt::Display for Expression<'_> {
+    /// Format the expression as a YARA condition.
+    ///
+    /// ```
+    /// # use yari_sys::parser::parse;
+    /// let (_, expr) = parse("r|(1 + 2) * #s00 == 3 and $s00 at 0").unwrap();
+    /// assert_eq!(expr.to_string(), "(1 + 2) * #s00 == 3 and $s00 at 0");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Function { name, args } => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expression::Value(name) => write!(f, "{}", name),
+            Expression::String {
+                operator,
+                prefix,
+                index,
+            } => match index {
+                Some(index) => write!(f, "{}{}[{}]", operator, prefix, index),
+                None => write!(f, "{}{}", operator, prefix),
+            },
+            Expression::StringAt { prefix, offset } => write!(f, "${} at {}", prefix, offset),
+            Expression::StringIn {
+                operator,
+                prefix,
+                lower,
+                upper,
+            } => write!(f, "{}{} in ({}..{})", operator, prefix, lower, upper),
+            Expression::Of {
+                quantifier,
+                strings,
+            } => {
+                if strings == &["*"] {
+                    write!(f, "{} of them", quantifier)
+                } else {
+                    let strings: Vec<String> = strings.iter().map(|s| format!("${}", s)).collect();
+                    write!(f, "{} of ({})", quantifier, strings.join(", "))
+                }
+            }
+            Expression::StringMatches(prefix) => write!(f, "matches(${})", prefix),
+            Expression::Integer(i) => write!(f, "{}", i),
+            Expression::Float(v) => write!(f, "{:?}", v),
+            Expression::Text(text) => write!(f, "\"{}\"", text),
+            Expression::Filesize => write!(f, "filesize"),
+            Expression::Entrypoint => write!(f, "entrypoint"),
+            // Boolean unary operators bind looser than the comparisons
+            Expression::Unary {
+                operator: operator @ (UnaryOperator::Not | UnaryOperator::Defined),
+                operand,
+            } => write!(
+                f,
+                "{}{}",
+                operator,
+                Operand(
+                    operand,
+                    operand.precedence() <= BinaryOperator::And.precedence()
+                )
+            ),
+            Expression::Unary { operator, operand } => {
+                write!(f, "{}{}", operator, Operand(operand, true))
+            }
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                // Operators are left associative, so the right operand of the same precedence
+                // has to be parenthesized too
+                write!(
+                    f,
+                    "{} {} {}",
+                    Operand(left, left.precedence() < operator.precedence()),
+                    operator,
+                    Operand(right, right.precedence() <= operator.precedence())
+                )
+            }
+            Expression::Complex(value) => write!(f, "{}", value),
+        }
+    }
+}
+
 fn whitespace(input: &str) -> IResult<&str, &str> {
     let chars = " \t\r\n";
     take_while(move |c| chars.contains(c))(input)
@@ -151,8 +472,16 @@ fn iterable_access_multi(input: &str) -> IResult<&str, &str> {
     ))(input)
 }
 
+/// Rule context of the expression, the rule can be qualified by a namespace (`namespace:rule|`).
 fn rule_context(input: &str) -> IResult<&str, &str> {
-    delimited(whitespace, terminated(identifier, char('|')), whitespace)(input)
+    delimited(
+        whitespace,
+        terminated(
+            recognize(pair(identifier, opt(pair(char(':'), identifier)))),
+            char('|'),
+        ),
+        whitespace,
+    )(input)
 }
 
 fn identifier_multi(input: &str) -> IResult<&str, &str> {
@@ -210,12 +539,31 @@ fn argument(input: &str) -> IResult<&str, Argument> {
     preceded(
         whitespace,
         alt((
-            map(string, Argument::String),
-            map(regexp, |(r, m)| Argument::Regexp(r, m)),
-            map_res(is_not(",)"), |i: &str| {
-                i.parse::<i64>().map(Argument::Integer)
+            map(
+                terminated(
+                    regexp,
+                    peek(preceded(whitespace, alt((char(','), char(')'))))),
+                ),
+                |(r, m)| Argument::Regexp(r, m),
+            ),
+            map(condition, |expr| match expr {
+                Expression::Text(s) => Argument::String(s),
+                Expression::Integer(i) => Argument::Integer(i),
+                Expression::Float(f) => Argument::Float(f),
+                // Negative literals are parsed as unary minus
+                Expression::Unary {
+                    operator: UnaryOperator::Minus,
+                    operand,
+                } => match *operand {
+                    Expression::Integer(i) => Argument::Integer(i.wrapping_neg()),
+                    Expression::Float(f) => Argument::Float(-f),
+                    operand => Argument::Expression(Box::new(Expression::Unary {
+                        operator: UnaryOperator::Minus,
+                        operand: Box::new(operand),
+                    })),
+                },
+                expr => Argument::Expression(Box::new(expr)),
             }),
-            map(double, Argument::Float),
         )),
     )(input)
 }
@@ -233,8 +581,51 @@ fn arguments(input: &str) -> IResult<&str, Vec<Argument>> {
     )(input)
 }
 
+/// Words reserved by YARA which can't be used as a value identifier.
+const KEYWORDS: &[&str] = &[
+    "all",
+    "and",
+    "any",
+    "at",
+    "contains",
+    "defined",
+    "endswith",
+    "entrypoint",
+    "false",
+    "filesize",
+    "for",
+    "icontains",
+    "iendswith",
+    "iequals",
+    "in",
+    "istartswith",
+    "matches",
+    "none",
+    "not",
+    "of",
+    "or",
+    "startswith",
+    "them",
+    "true",
+];
+
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(word), not(satisfy(is_identifier_char)))
+}
+
+fn is_value_identifier(identifier: &str) -> bool {
+    !identifier.starts_with(|c: char| c.is_ascii_digit()) && !KEYWORDS.contains(&identifier)
+}
+
+fn function_term(input: &str) -> IResult<&str, (&str, Vec<Argument>)> {
+    pair(
+        verify(identifier_multi, |name: &str| !KEYWORDS.contains(&name)),
+        arguments,
+    )(input)
+}
+
 fn function_call(input: &str) -> IResult<&str, (&str, Vec<Argument>)> {
-    let res = delimited(whitespace, pair(identifier_multi, arguments), whitespace)(input);
+    let res = delimited(whitespace, function_term, whitespace)(input);
 
     if let Ok(ref inner) = res {
         if !inner.0.is_empty() {
@@ -248,8 +639,14 @@ fn function_call(input: &str) -> IResult<&str, (&str, Vec<Argument>)> {
     res
 }
 
+fn value_term(input: &str) -> IResult<&str, &str> {
+    verify(identifier_multi, |identifier: &str| {
+        is_value_identifier(identifier.split(['.', '[']).next().unwrap_or_default())
+    })(input)
+}
+
 fn value_access(input: &str) -> IResult<&str, &str> {
-    let res = delimited(whitespace, identifier_multi, whitespace)(input);
+    let res = delimited(whitespace, value_term, whitespace)(input);
 
     if let Ok(ref inner) = res {
         if !inner.0.is_empty() {
@@ -263,59 +660,440 @@ fn value_access(input: &str) -> IResult<&str, &str> {
     res
 }
 
+/// Identifier of a string as written after the operator: a name (`s00`), a name prefix with
+/// wildcard (`s*`, `*`) or an index of an anonymous string (`[1]`).
+fn string_identifier(input: &str) -> IResult<&str, &str> {
+    // Star followed by an operand is a multiplication, not a wildcard
+    let wildcard = || {
+        terminated(
+            char('*'),
+            not(satisfy(|c| is_identifier_char(c) || c == '(')),
+        )
+    };
+
+    alt((
+        recognize(delimited(char('['), digit1, char(']'))),
+        recognize(pair(identifier, opt(wildcard()))),
+        recognize(wildcard()),
+    ))(input)
+}
+
 fn string_index(input: &str) -> IResult<&str, i64> {
     cut(verify(array_access, |i: &i64| *i > 0))(input)
 }
 
-fn string_operation(input: &str) -> IResult<&str, (StrOperation, &str, Option<i64>)> {
-    let res = delimited(
-        whitespace,
-        alt((
-            map(
-                pair(
-                    verify(
-                        map_res(anychar, StrOperation::try_from),
-                        |op: &StrOperation| *op == StrOperation::MatchesOnce,
-                    ),
-                    identifier,
+fn string_term(input: &str) -> IResult<&str, (StrOperation, &str, Option<i64>)> {
+    alt((
+        map(
+            pair(
+                verify(
+                    map_res(anychar, StrOperation::try_from),
+                    |op: &StrOperation| *op == StrOperation::MatchesOnce,
                 ),
-                |(op, identifier)| (op, identifier, None),
+                string_identifier,
             ),
-            map(
-                pair(
-                    verify(
-                        map_res(anychar, StrOperation::try_from),
-                        |op: &StrOperation| *op == StrOperation::MatchesCount,
-                    ),
-                    identifier,
+            |(op, identifier)| (op, identifier, None),
+        ),
+        map(
+            pair(
+                verify(
+                    map_res(anychar, StrOperation::try_from),
+                    |op: &StrOperation| *op == StrOperation::MatchesCount,
                 ),
-                |(op, identifier)| (op, identifier, None),
+                string_identifier,
             ),
-            map(
-                tuple((
-                    verify(
-                        map_res(anychar, StrOperation::try_from),
-                        |op: &StrOperation| *op == StrOperation::MatchLength,
-                    ),
-                    identifier,
-                    string_index,
-                )),
-                |(op, identifier, index)| (op, identifier, Some(index)),
+            |(op, identifier)| (op, identifier, None),
+        ),
+        map(
+            tuple((
+                verify(
+                    map_res(anychar, StrOperation::try_from),
+                    |op: &StrOperation| *op == StrOperation::MatchLength,
+                ),
+                string_identifier,
+                string_index,
+            )),
+            |(op, identifier, index)| (op, identifier, Some(index)),
+        ),
+        map(
+            tuple((
+                verify(
+                    map_res(anychar, StrOperation::try_from),
+                    |op: &StrOperation| *op == StrOperation::MatchOffset,
+                ),
+                string_identifier,
+                string_index,
+            )),
+            |(op, identifier, index)| (op, identifier, Some(index)),
+        ),
+    ))(input)
+}
+
+/// Range of offsets (`(0..100)`), both bounds are inclusive.
+fn range(input: &str) -> IResult<&str, (Expression, Expression)> {
+    delimited(
+        pair(char('('), whitespace),
+        separated_pair(
+            bitwise_or_expression,
+            preceded(whitespace, tag("..")),
+            bitwise_or_expression,
+        ),
+        pair(whitespace, char(')')),
+    )(input)
+}
+
+fn string_at(input: &str) -> IResult<&str, Expression> {
+    map(
+        separated_pair(
+            preceded(char('$'), string_identifier),
+            pair(whitespace, keyword("at")),
+            bitwise_or_expression,
+        ),
+        |(prefix, offset)| Expression::StringAt {
+            prefix,
+            offset: Box::new(offset),
+        },
+    )(input)
+}
+
+fn string_in(input: &str) -> IResult<&str, Expression> {
+    map(
+        tuple((
+            alt((
+                value(StrOperation::MatchesOnce, char('$')),
+                value(StrOperation::MatchesCount, char('#')),
+            )),
+            string_identifier,
+            delimited(whitespace, keyword("in"), whitespace),
+            range,
+        )),
+        |(operator, prefix, _, (lower, upper))| Expression::StringIn {
+            operator,
+            prefix,
+            lower: Box::new(lower),
+            upper: Box::new(upper),
+        },
+    )(input)
+}
+
+/// Number of strings in the `of` expression, it is limited to the primary values to avoid left
+/// recursion.
+fn quantity(input: &str) -> IResult<&str, Expression> {
+    alt((
+        map(integer_literal, Expression::Integer),
+        delimited(char('('), condition, preceded(whitespace, char(')'))),
+        map(value_term, Expression::Value),
+    ))(input)
+}
+
+fn quantifier(input: &str) -> IResult<&str, Quantifier> {
+    alt((
+        map(keyword("all"), |_| Quantifier::All),
+        map(keyword("any"), |_| Quantifier::Any),
+        map(keyword("none"), |_| Quantifier::None),
+        map(terminated(quantity, pair(whitespace, char('%'))), |q| {
+            Quantifier::Percent(Box::new(q))
+        }),
+        map(quantity, |q| Quantifier::Count(Box::new(q))),
+    ))(input)
+}
+
+/// Set of strings, `them` is represented by the `*` wildcard.
+fn string_set(input: &str) -> IResult<&str, Vec<&str>> {
+    alt((
+        map(keyword("them"), |_| vec!["*"]),
+        delimited(
+            pair(char('('), whitespace),
+            separated_list1(
+                tuple((whitespace, char(','), whitespace)),
+                preceded(char('$'), string_identifier),
             ),
-            map(
-                tuple((
-                    verify(
-                        map_res(anychar, StrOperation::try_from),
-                        |op: &StrOperation| *op == StrOperation::MatchOffset,
-                    ),
-                    identifier,
-                    string_index,
-                )),
-                |(op, identifier, index)| (op, identifier, Some(index)),
+            pair(whitespace, char(')')),
+        ),
+    ))(input)
+}
+
+fn of_expression(input: &str) -> IResult<&str, Expression> {
+    map(
+        separated_pair(
+            quantifier,
+            delimited(whitespace, keyword("of"), whitespace),
+            string_set,
+        ),
+        |(quantifier, strings)| Expression::Of {
+            quantifier,
+            strings,
+        },
+    )(input)
+}
+
+fn string_matches(input: &str) -> IResult<&str, &str> {
+    preceded(
+        pair(keyword("matches"), whitespace),
+        delimited(
+            pair(char('('), whitespace),
+            preceded(char('$'), string_identifier),
+            pair(whitespace, char(')')),
+        ),
+    )(input)
+}
+
+fn string_operation(input: &str) -> IResult<&str, (StrOperation, &str, Option<i64>)> {
+    let res = delimited(whitespace, string_term, whitespace)(input);
+
+    // Check if we still have unconsumed input and return error if so
+    if let Ok(ref inner) = res {
+        if !inner.0.is_empty() {
+            return Err(Err::Error(nom::error::Error::new(
+                inner.0,
+                ErrorKind::Verify,
+            )));
+        }
+    }
+
+    res
+}
+
+/// Integer literal in decimal, hexadecimal (`0x`) or octal (`0o`) notation. Decimal numbers
+/// accept the `KB` and `MB` multipliers.
+fn integer_literal(input: &str) -> IResult<&str, i64> {
+    terminated(
+        alt((
+            map_res(preceded(tag("0x"), hex_digit1), |i| {
+                i64::from_str_radix(i, 16)
+            }),
+            map_res(preceded(tag("0o"), oct_digit1), |i| {
+                i64::from_str_radix(i, 8)
+            }),
+            map_res(
+                pair(digit1, opt(alt((tag("KB"), tag("MB"))))),
+                |(i, unit): (&str, Option<&str>)| {
+                    let multiplier = match unit {
+                        Some("KB") => 1024,
+                        Some("MB") => 1024 * 1024,
+                        _ => 1,
+                    };
+                    i.parse::<i64>()
+                        .ok()
+                        .and_then(|i| i.checked_mul(multiplier))
+                        .ok_or(ErrorKind::MapRes)
+                },
             ),
         )),
+        not(satisfy(is_identifier_char)),
+    )(input)
+}
+
+fn float_literal(input: &str) -> IResult<&str, f64> {
+    terminated(
+        map_res(recognize(tuple((digit1, char('.'), digit1))), f64::from_str),
+        not(satisfy(is_identifier_char)),
+    )(input)
+}
+
+fn primary_expression(input: &str) -> IResult<&str, Expression> {
+    preceded(
         whitespace,
-    )(input);
+        alt((
+            of_expression,
+            delimited(char('('), condition, preceded(whitespace, char(')'))),
+            string_at,
+            string_in,
+            map(string_term, |s| Expression::String {
+                operator: s.0,
+                prefix: s.1,
+                index: s.2,
+            }),
+            map(float_literal, Expression::Float),
+            map(integer_literal, Expression::Integer),
+            map(string, Expression::Text),
+            map(keyword("true"), |_| Expression::Integer(1)),
+            map(keyword("false"), |_| Expression::Integer(0)),
+            map(keyword("filesize"), |_| Expression::Filesize),
+            map(keyword("entrypoint"), |_| Expression::Entrypoint),
+            map(string_matches, Expression::StringMatches),
+            map(function_term, |func| Expression::Function {
+                name: func.0,
+                args: func.1,
+            }),
+            map(value_term, Expression::Value),
+        )),
+    )(input)
+}
+
+fn unary_expression(input: &str) -> IResult<&str, Expression> {
+    alt((
+        map(
+            pair(
+                preceded(
+                    whitespace,
+                    alt((
+                        value(UnaryOperator::Minus, char('-')),
+                        value(UnaryOperator::BitwiseNot, char('~')),
+                    )),
+                ),
+                unary_expression,
+            ),
+            |(operator, operand)| Expression::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+        ),
+        primary_expression,
+    ))(input)
+}
+
+/// Parse a chain of left associative binary operations of the same precedence.
+fn binary_expression<'a>(
+    input: &'a str,
+    operand: fn(&'a str) -> IResult<&'a str, Expression<'a>>,
+    operator: fn(&'a str) -> IResult<&'a str, BinaryOperator>,
+) -> IResult<&'a str, Expression<'a>> {
+    let (mut input, mut left) = operand(input)?;
+
+    loop {
+        match pair(preceded(whitespace, operator), operand)(input) {
+            Ok((rest, (operator, right))) => {
+                left = Expression::Binary {
+                    operator,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                };
+                input = rest;
+            }
+            Err(Err::Error(_)) => return Ok((input, left)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn multiplicative_operator(input: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::Mul, char('*')),
+        value(BinaryOperator::Div, char('\\')),
+        value(BinaryOperator::Mod, char('%')),
+    ))(input)
+}
+
+fn multiplicative_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, unary_expression, multiplicative_operator)
+}
+
+fn additive_operator(input: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::Add, char('+')),
+        value(BinaryOperator::Sub, char('-')),
+    ))(input)
+}
+
+fn additive_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, multiplicative_expression, additive_operator)
+}
+
+fn shift_operator(input: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::ShiftLeft, tag("<<")),
+        value(BinaryOperator::ShiftRight, tag(">>")),
+    ))(input)
+}
+
+fn shift_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, additive_expression, shift_operator)
+}
+
+fn bitwise_and_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, shift_expression, |input| {
+        value(BinaryOperator::BitwiseAnd, char('&'))(input)
+    })
+}
+
+fn bitwise_xor_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, bitwise_and_expression, |input| {
+        value(BinaryOperator::BitwiseXor, char('^'))(input)
+    })
+}
+
+fn bitwise_or_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, bitwise_xor_expression, |input| {
+        value(BinaryOperator::BitwiseOr, char('|'))(input)
+    })
+}
+
+fn relational_operator(input: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::LessEqual, tag("<=")),
+        value(BinaryOperator::GreaterEqual, tag(">=")),
+        value(BinaryOperator::Less, terminated(char('<'), not(char('<')))),
+        value(
+            BinaryOperator::Greater,
+            terminated(char('>'), not(char('>'))),
+        ),
+    ))(input)
+}
+
+fn relational_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, bitwise_or_expression, relational_operator)
+}
+
+fn equality_operator(input: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::Equal, tag("==")),
+        value(BinaryOperator::NotEqual, tag("!=")),
+        value(BinaryOperator::Contains, keyword("contains")),
+        value(BinaryOperator::IContains, keyword("icontains")),
+        value(BinaryOperator::StartsWith, keyword("startswith")),
+        value(BinaryOperator::IStartsWith, keyword("istartswith")),
+        value(BinaryOperator::EndsWith, keyword("endswith")),
+        value(BinaryOperator::IEndsWith, keyword("iendswith")),
+        value(BinaryOperator::IEquals, keyword("iequals")),
+    ))(input)
+}
+
+fn equality_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, relational_expression, equality_operator)
+}
+
+fn not_expression(input: &str) -> IResult<&str, Expression> {
+    alt((
+        map(
+            pair(
+                preceded(
+                    whitespace,
+                    alt((
+                        value(UnaryOperator::Not, keyword("not")),
+                        value(UnaryOperator::Defined, keyword("defined")),
+                    )),
+                ),
+                not_expression,
+            ),
+            |(operator, operand)| Expression::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+        ),
+        equality_expression,
+    ))(input)
+}
+
+fn and_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, not_expression, |input| {
+        value(BinaryOperator::And, keyword("and"))(input)
+    })
+}
+
+fn or_expression(input: &str) -> IResult<&str, Expression> {
+    binary_expression(input, and_expression, |input| {
+        value(BinaryOperator::Or, keyword("or"))(input)
+    })
+}
+
+/// Boolean, arithmetic and bitwise expression following the YARA operator precedence.
+fn condition(input: &str) -> IResult<&str, Expression> {
+    or_expression(input)
+}
+
+fn condition_full(input: &str) -> IResult<&str, Expression> {
+    let res = terminated(condition, whitespace)(input);
 
     // Check if we still have unconsumed input and return error if so
     if let Ok(ref inner) = res {
@@ -349,6 +1127,7 @@ fn expression(input: &str) -> IResult<&str, (Option<&str>, Expression)> {
                     args: func.1,
                 }),
                 map(value_access, Expression::Value),
+                condition_full,
             )),
         ),
         map(pair(rule_context, complex_value), |(rule, value)| {
@@ -657,16 +1436,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rule_context_with_namespace() {
+        let res = parse("ns:rule|$s00");
+        assert_eq!(
+            res,
+            Ok((
+                Some("ns:rule"),
+                Expression::String {
+                    operator: StrOperation::MatchesOnce,
+                    prefix: "s00",
+                    index: None,
+                }
+            ))
+        );
+
+        assert_eq!(rule_context("ns:rule|"), Ok(("", "ns:rule")));
+        assert!(rule_context("ns:|").is_err());
+    }
+
     #[test]
     fn test_function_call_with_hexa_arguments() {
-        // Hexa arguments are not implemented yet, however parsing should still be successful to allow fallback evaluation
         let res = parse("rule|hash.sha256(0x0, 0x400)");
         assert_eq!(
             res,
-            Ok((Some("rule"), Expression::Complex("hash.sha256(0x0, 0x400)")))
+            Ok((
+                Some("rule"),
+                Expression::Function {
+                    name: "hash.sha256",
+                    args: vec![Argument::Integer(0), Argument::Integer(0x400)]
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_function_call_with_negative_argument() {
+        let res = parse("math.abs(-5)");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Function {
+                    name: "math.abs",
+                    args: vec![Argument::Integer(-5)]
+                }
+            ))
         );
     }
 
+    #[test]
+    fn test_function_call_with_expression_arguments() {
+        let res = parse("hash.md5(0, filesize)");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Function {
+                    name: "hash.md5",
+                    args: vec![
+                        Argument::Integer(0),
+                        Argument::Expression(Box::new(Expression::Filesize))
+                    ]
+                }
+            ))
+        );
+
+        let res = parse("math.entropy(pe.sections[0].raw_data_offset, 2 * entrypoint)");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Function {
+                    name: "math.entropy",
+                    args: vec![
+                        Argument::Expression(Box::new(Expression::Value(
+                            "pe.sections[0].raw_data_offset"
+                        ))),
+                        Argument::Expression(Box::new(Expression::Binary {
+                            operator: BinaryOperator::Mul,
+                            left: Box::new(Expression::Integer(2)),
+                            right: Box::new(Expression::Entrypoint),
+                        }))
+                    ]
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_function_call_with_nested_function_call() {
+        let res = parse("math.in_range(math.entropy(0, filesize), 7.0, 8.0)");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Function {
+                    name: "math.in_range",
+                    args: vec![
+                        Argument::Expression(Box::new(Expression::Function {
+                            name: "math.entropy",
+                            args: vec![
+                                Argument::Integer(0),
+                                Argument::Expression(Box::new(Expression::Filesize))
+                            ]
+                        })),
+                        Argument::Float(7.0),
+                        Argument::Float(8.0)
+                    ]
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_function_call_with_regexp_expression_is_invalid() {
+        assert!(argument("/abc/ + 1").is_err());
+    }
+
     #[test]
     fn test_identifier_array_access() {
         let res = identifier_multi("pe.sections[0].name");
@@ -971,6 +1858,211 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_matches() {
+        assert_eq!(
+            parse("r|matches($s00)"),
+            Ok((Some("r"), Expression::StringMatches("s00")))
+        );
+        assert_eq!(
+            parse("r|matches ( $s00 )"),
+            Ok((Some("r"), Expression::StringMatches("s00")))
+        );
+        assert_eq!(
+            parse("r|#s00 == matches($s00)"),
+            Ok((
+                Some("r"),
+                Expression::Binary {
+                    operator: BinaryOperator::Equal,
+                    left: Box::new(Expression::String {
+                        operator: StrOperation::MatchesCount,
+                        prefix: "s00",
+                        index: None,
+                    }),
+                    right: Box::new(Expression::StringMatches("s00")),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string_wildcard_and_anonymous() {
+        let string = |operator, prefix, index| Expression::String {
+            operator,
+            prefix,
+            index,
+        };
+
+        assert_eq!(
+            parse("r|#s*"),
+            Ok((Some("r"), string(StrOperation::MatchesCount, "s*", None)))
+        );
+        assert_eq!(
+            parse("r|$*"),
+            Ok((Some("r"), string(StrOperation::MatchesOnce, "*", None)))
+        );
+        assert_eq!(
+            parse("r|$[2]"),
+            Ok((Some("r"), string(StrOperation::MatchesOnce, "[2]", None)))
+        );
+        assert_eq!(
+            parse("r|@[1][3]"),
+            Ok((Some("r"), string(StrOperation::MatchOffset, "[1]", Some(3))))
+        );
+        assert_eq!(
+            parse("r|#s00*2"),
+            Ok((
+                Some("r"),
+                Expression::Binary {
+                    operator: BinaryOperator::Mul,
+                    left: Box::new(string(StrOperation::MatchesCount, "s00", None)),
+                    right: Box::new(Expression::Integer(2)),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string_at_and_in() {
+        assert_eq!(
+            parse("r|$a at entrypoint + 1"),
+            Ok((
+                Some("r"),
+                Expression::StringAt {
+                    prefix: "a",
+                    offset: Box::new(Expression::Binary {
+                        operator: BinaryOperator::Add,
+                        left: Box::new(Expression::Entrypoint),
+                        right: Box::new(Expression::Integer(1)),
+                    }),
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|#a in (0..filesize) == 2"),
+            Ok((
+                Some("r"),
+                Expression::Binary {
+                    operator: BinaryOperator::Equal,
+                    left: Box::new(Expression::StringIn {
+                        operator: StrOperation::MatchesCount,
+                        prefix: "a",
+                        lower: Box::new(Expression::Integer(0)),
+                        upper: Box::new(Expression::Filesize),
+                    }),
+                    right: Box::new(Expression::Integer(2)),
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|$a in ( 10 .. 20 ) and $b"),
+            Ok((
+                Some("r"),
+                Expression::Binary {
+                    operator: BinaryOperator::And,
+                    left: Box::new(Expression::StringIn {
+                        operator: StrOperation::MatchesOnce,
+                        prefix: "a",
+                        lower: Box::new(Expression::Integer(10)),
+                        upper: Box::new(Expression::Integer(20)),
+                    }),
+                    right: Box::new(Expression::String {
+                        operator: StrOperation::MatchesOnce,
+                        prefix: "b",
+                        index: None,
+                    }),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_of_expression() {
+        assert_eq!(
+            parse("r|any of ($s*, $a)"),
+            Ok((
+                Some("r"),
+                Expression::Of {
+                    quantifier: Quantifier::Any,
+                    strings: vec!["s*", "a"],
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|2 of them"),
+            Ok((
+                Some("r"),
+                Expression::Of {
+                    quantifier: Quantifier::Count(Box::new(Expression::Integer(2))),
+                    strings: vec!["*"],
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|50% of them"),
+            Ok((
+                Some("r"),
+                Expression::Of {
+                    quantifier: Quantifier::Percent(Box::new(Expression::Integer(50))),
+                    strings: vec!["*"],
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|not all of them"),
+            Ok((
+                Some("r"),
+                Expression::Unary {
+                    operator: UnaryOperator::Not,
+                    operand: Box::new(Expression::Of {
+                        quantifier: Quantifier::All,
+                        strings: vec!["*"],
+                    }),
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|(1 + 1) of ($[1], $[2])"),
+            Ok((
+                Some("r"),
+                Expression::Of {
+                    quantifier: Quantifier::Count(Box::new(Expression::Binary {
+                        operator: BinaryOperator::Add,
+                        left: Box::new(Expression::Integer(1)),
+                        right: Box::new(Expression::Integer(1)),
+                    })),
+                    strings: vec!["[1]", "[2]"],
+                }
+            ))
+        );
+        assert_eq!(
+            parse("r|(1 + 1) * 2").map(|(_, e)| matches!(e, Expression::Binary { .. })),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for expr in [
+            "1 + 2 * 3 == 7 or not 4 < 5 and 1",
+            "1 - 2 - 3",
+            "1 - (2 - 3)",
+            "-(1 + 2) \\ ~3",
+            "not defined pe.number_of_sections",
+            "math.entropy(0, filesize) >= 7.0",
+            "hash.md5(0, math.min(10, filesize)) == \"abc\"",
+            "cuckoo.filesystem.file_access(/a\\/b/is)",
+            "@s00[2] - @s00[1] > !s00[1]",
+            "any of ($s*, $[1]) and 2 of them",
+            "50% of them",
+            "#a in (0..filesize) == matches($a)",
+            "$a at entrypoint + 1",
+        ] {
+            let (_, parsed) = parse(expr).unwrap();
+            assert_eq!(parsed.to_string(), expr);
+        }
+    }
+
     #[test]
     fn test_complex_expression_eq() {
         let res = expression("rule|pe.num_of_sections == 4");
@@ -978,7 +2070,14 @@ mod tests {
             res,
             Ok((
                 "",
-                (Some("rule"), Expression::Complex("pe.num_of_sections == 4")),
+                (
+                    Some("rule"),
+                    Expression::Binary {
+                        operator: BinaryOperator::Equal,
+                        left: Box::new(Expression::Value("pe.num_of_sections")),
+                        right: Box::new(Expression::Integer(4)),
+                    }
+                ),
             ))
         );
     }
@@ -992,7 +2091,19 @@ mod tests {
                 "",
                 (
                     Some("rule"),
-                    Expression::Complex("$s00 and pe.num_of_sections == 4")
+                    Expression::Binary {
+                        operator: BinaryOperator::And,
+                        left: Box::new(Expression::String {
+                            operator: StrOperation::MatchesOnce,
+                            prefix: "s00",
+                            index: None,
+                        }),
+                        right: Box::new(Expression::Binary {
+                            operator: BinaryOperator::Equal,
+                            left: Box::new(Expression::Value("pe.num_of_sections")),
+                            right: Box::new(Expression::Integer(4)),
+                        }),
+                    }
                 ),
             ))
         );
@@ -1003,13 +2114,231 @@ mod tests {
         let res = expression("pe.num_of_sections == 4");
         assert_eq!(
             res,
-            Err(Err::Error(nom::error::Error::new(
-                ".num_of_sections == 4",
-                ErrorKind::Char
-            ))),
+            Ok((
+                "",
+                (
+                    None,
+                    Expression::Binary {
+                        operator: BinaryOperator::Equal,
+                        left: Box::new(Expression::Value("pe.num_of_sections")),
+                        right: Box::new(Expression::Integer(4)),
+                    }
+                ),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_complex_expression_unsupported() {
+        let res = expression("rule|for any i in (1..#s00): (@s00[i] > 0)");
+        assert_eq!(
+            res,
+            Ok((
+                "",
+                (
+                    Some("rule"),
+                    Expression::Complex("for any i in (1..#s00): (@s00[i] > 0)")
+                ),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_complex_expression_unsupported_without_context() {
+        let res = expression("for any i in (1..#s00): (@s00[i] > 0)");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let res = parse("1 + 2 * 3 == 7 or not 4 < 5 and true");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Binary {
+                    operator: BinaryOperator::Or,
+                    left: Box::new(Expression::Binary {
+                        operator: BinaryOperator::Equal,
+                        left: Box::new(Expression::Binary {
+                            operator: BinaryOperator::Add,
+                            left: Box::new(Expression::Integer(1)),
+                            right: Box::new(Expression::Binary {
+                                operator: BinaryOperator::Mul,
+                                left: Box::new(Expression::Integer(2)),
+                                right: Box::new(Expression::Integer(3)),
+                            }),
+                        }),
+                        right: Box::new(Expression::Integer(7)),
+                    }),
+                    right: Box::new(Expression::Binary {
+                        operator: BinaryOperator::And,
+                        left: Box::new(Expression::Unary {
+                            operator: UnaryOperator::Not,
+                            operand: Box::new(Expression::Binary {
+                                operator: BinaryOperator::Less,
+                                left: Box::new(Expression::Integer(4)),
+                                right: Box::new(Expression::Integer(5)),
+                            }),
+                        }),
+                        right: Box::new(Expression::Integer(1)),
+                    }),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_left_associativity() {
+        let res = parse("10 - 4 - 3");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Binary {
+                    operator: BinaryOperator::Sub,
+                    left: Box::new(Expression::Binary {
+                        operator: BinaryOperator::Sub,
+                        left: Box::new(Expression::Integer(10)),
+                        right: Box::new(Expression::Integer(4)),
+                    }),
+                    right: Box::new(Expression::Integer(3)),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let res = parse("(1 + 2) * 3");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Binary {
+                    operator: BinaryOperator::Mul,
+                    left: Box::new(Expression::Binary {
+                        operator: BinaryOperator::Add,
+                        left: Box::new(Expression::Integer(1)),
+                        right: Box::new(Expression::Integer(2)),
+                    }),
+                    right: Box::new(Expression::Integer(3)),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_shift_and_relational() {
+        let res = parse("1 << 2 < 5");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Binary {
+                    operator: BinaryOperator::Less,
+                    left: Box::new(Expression::Binary {
+                        operator: BinaryOperator::ShiftLeft,
+                        left: Box::new(Expression::Integer(1)),
+                        right: Box::new(Expression::Integer(2)),
+                    }),
+                    right: Box::new(Expression::Integer(5)),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        let res = parse("-~filesize");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Unary {
+                    operator: UnaryOperator::Minus,
+                    operand: Box::new(Expression::Unary {
+                        operator: UnaryOperator::BitwiseNot,
+                        operand: Box::new(Expression::Filesize),
+                    }),
+                },
+            ))
+        );
+
+        let res = parse("defined pe.entry_point");
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Unary {
+                    operator: UnaryOperator::Defined,
+                    operand: Box::new(Expression::Value("pe.entry_point")),
+                },
+            ))
         );
     }
 
+    #[test]
+    fn test_integer_literals() {
+        assert_eq!(integer_literal("0x2200"), Ok(("", 0x2200)));
+        assert_eq!(integer_literal("0o17"), Ok(("", 0o17)));
+        assert_eq!(integer_literal("10KB"), Ok(("", 10 * 1024)));
+        assert_eq!(integer_literal("2MB"), Ok(("", 2 * 1024 * 1024)));
+        assert!(integer_literal("12abc").is_err());
+        assert!(integer_literal("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_float_literal() {
+        assert_eq!(float_literal("7.5"), Ok(("", 7.5)));
+        assert!(float_literal("7").is_err());
+    }
+
+    #[test]
+    fn test_literal_values() {
+        assert_eq!(parse("1"), Ok((None, Expression::Integer(1))));
+        assert_eq!(parse("false"), Ok((None, Expression::Integer(0))));
+        assert_eq!(parse("entrypoint"), Ok((None, Expression::Entrypoint)));
+        assert_eq!(parse(r#""a\"b""#), Ok((None, Expression::Text(r#"a\"b"#))));
+    }
+
+    #[test]
+    fn test_string_operators() {
+        let res = parse(r#"pe.sections[0].name icontains ".TEXT""#);
+        assert_eq!(
+            res,
+            Ok((
+                None,
+                Expression::Binary {
+                    operator: BinaryOperator::IContains,
+                    left: Box::new(Expression::Value("pe.sections[0].name")),
+                    right: Box::new(Expression::Text(".TEXT")),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_keyword_prefixed_identifier() {
+        assert_eq!(parse("notes"), Ok((None, Expression::Value("notes"))));
+        assert_eq!(
+            parse("not notes"),
+            Ok((
+                None,
+                Expression::Unary {
+                    operator: UnaryOperator::Not,
+                    operand: Box::new(Expression::Value("notes")),
+                },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_keyword_is_not_value() {
+        assert!(parse("and").is_err());
+        assert!(parse("1 and or 2").is_err());
+    }
+
     #[test]
     fn test_empty() {
         let res = expression("");
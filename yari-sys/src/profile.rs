@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+/// Statistics of the string collected while scanning the sample.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct StringStats {
+    pub(crate) atom_matches: u64,
+    pub(crate) match_time: Duration,
+}
+
+/// Cost of the rule in the profiled scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleProfile {
+    /// Rule name qualified by the namespace
+    pub rule: String,
+    /// Cost reported by libyara, `None` without the `profiling` feature
+    pub cost: Option<u64>,
+    /// Number of atom matches of all the rule strings
+    pub atom_matches: u64,
+    /// Time spent verifying matches of all the rule strings
+    pub match_time: Duration,
+}
+
+/// Cost of the string in the profiled scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringProfile {
+    /// Rule name qualified by the namespace
+    pub rule: String,
+    pub identifier: String,
+    /// Number of atom matches, every one of them has to be verified
+    pub atom_matches: u64,
+    /// Time spent verifying the atom matches
+    pub match_time: Duration,
+}
+
+/// Profiling results of the scan, the most expensive rules and strings go first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub rules: Vec<RuleProfile>,
+    pub strings: Vec<StringProfile>,
+}
+
+impl Profile {
+    /// Sort the rules by the libyara cost and the strings by the verification time.
+    ///
+    /// Rules without the libyara cost are ordered by the verification time of their strings.
+    pub(crate) fn sort(&mut self) {
+        self.rules.sort_by(|a, b| {
+            b.cost
+                .cmp(&a.cost)
+                .then(b.match_time.cmp(&a.match_time))
+                .then(a.rule.cmp(&b.rule))
+        });
+        self.strings.sort_by(|a, b| {
+            b.match_time
+                .cmp(&a.match_time)
+                .then(b.atom_matches.cmp(&a.atom_matches))
+                .then(a.rule.cmp(&b.rule))
+        });
+    }
+}
@@ -0,0 +1,231 @@
+use crate::dump::ModuleNode;
+use crate::dump::ObjectKind;
+use crate::YrValue;
+use std::fmt;
+
+/// Number of array items rendered unless the full output is requested.
+pub const DEFAULT_MAX_ITEMS: usize = 16;
+
+const INDENT: &str = "  ";
+
+// ANSI styles of the rendered parts
+const STYLE_NUMBER: &str = "36";
+const STYLE_STRING: &str = "32";
+const STYLE_KIND: &str = "34";
+const STYLE_UNDEFINED: &str = "35";
+const STYLE_NOTE: &str = "2";
+const STYLE_ERROR: &str = "1;31";
+
+/// Human readable rendering of evaluated values and module dumps.
+///
+/// Structures, arrays and dictionaries are rendered as indented trees with one member per line.
+/// Integers are shown in decimal and hexadecimal, undefined values as `undefined` (strings are
+/// always quoted, so the mark can not be mistaken for a value).
+///
+/// ```
+/// # use yari_sys::render::Renderer;
+/// # use yari_sys::{YrValue, YR_UNDEFINED};
+/// let renderer = Renderer::default();
+/// assert_eq!(renderer.render(&YrValue::Integer(332)), "332 (0x14c)");
+/// assert_eq!(renderer.render(&YrValue::Integer(YR_UNDEFINED)), "undefined");
+/// assert_eq!(renderer.render(&YrValue::Bytes(vec![0x4d, 0xff])), "b\"M\\xff\"");
+///
+/// let sections = YrValue::Array(vec![YrValue::String(Some(".text".to_string())); 20]);
+/// let rendered = renderer.render(&sections);
+/// assert!(rendered.starts_with("array[20]\n  [0]: \".text\"\n"));
+/// assert!(rendered.ends_with("\n  [15]: \".text\"\n  ... 4 more items"));
+/// assert_eq!(renderer.with_full(true).render(&sections).lines().count(), 21);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Renderer {
+    /// Render all array items
+    pub full: bool,
+    /// Highlight the output with ANSI escape sequences
+    pub color: bool,
+    /// Number of array items rendered if the output is not full
+    pub max_items: usize,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            full: false,
+            color: false,
+            max_items: DEFAULT_MAX_ITEMS,
+        }
+    }
+}
+
+impl Renderer {
+    pub fn with_full(mut self, full: bool) -> Self {
+        self.full = full;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Render the `value`, nested values are placed on the following lines.
+    pub fn render(&self, value: &YrValue) -> String {
+        let mut lines = Vec::new();
+        self.write_value(&mut lines, String::new(), value, 0);
+        lines.join("\n")
+    }
+
+    /// Render the module structure with the values for the current sample.
+    ///
+    /// Functions are rendered with their prototypes (e.g. `function (s) | (i) -> integer`).
+    pub fn render_node(&self, node: &ModuleNode) -> String {
+        let mut lines = Vec::new();
+        self.write_node(&mut lines, format!("{}: ", node.name), node, 0);
+        lines.join("\n")
+    }
+
+    /// Render the error message, highlighted so it stands out from the values.
+    ///
+    /// ```
+    /// # use yari_sys::render::Renderer;
+    /// # use yari_sys::YariError;
+    /// let renderer = Renderer::default();
+    /// assert_eq!(renderer.render_error(&YariError::EvalError), "error: cannot evaluate the expression");
+    /// ```
+    pub fn render_error(&self, error: &dyn fmt::Display) -> String {
+        self.paint(STYLE_ERROR, &format!("error: {}", error))
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Value of integers, floats and strings, `None` for the other values.
+    fn scalar(&self, value: &YrValue) -> Option<String> {
+        let rendered = match value {
+            _ if value.is_undefined() => self.paint(STYLE_UNDEFINED, "undefined"),
+            YrValue::Integer(i) => {
+                let sign = if *i < 0 { "-" } else { "" };
+                let text = format!("{} ({}{:#x})", i, sign, i.unsigned_abs());
+                self.paint(STYLE_NUMBER, &text)
+            }
+            YrValue::Float(f) => self.paint(STYLE_NUMBER, &format!("{:?}", f)),
+            YrValue::String(Some(s)) => {
+                let text = format!("\"{}\"", YrValue::escape_bytes(s.as_bytes()));
+                self.paint(STYLE_STRING, &text)
+            }
+            YrValue::Bytes(b) => {
+                let text = format!("b\"{}\"", YrValue::escape_bytes(b));
+                self.paint(STYLE_STRING, &text)
+            }
+            _ => return None,
+        };
+        Some(rendered)
+    }
+
+    /// Number of the items rendered out of `len`.
+    fn shown_items(&self, len: usize) -> usize {
+        if self.full {
+            len
+        } else {
+            len.min(self.max_items)
+        }
+    }
+
+    fn write_omitted(&self, lines: &mut Vec<String>, depth: usize, omitted: usize) {
+        if omitted > 0 {
+            let note = format!("... {} more items", omitted);
+            lines.push(format!(
+                "{}{}",
+                INDENT.repeat(depth),
+                self.paint(STYLE_NOTE, &note)
+            ));
+        }
+    }
+
+    /// Write the `value` on the line starting with the `prefix` (indentation and label).
+    fn write_value(&self, lines: &mut Vec<String>, prefix: String, value: &YrValue, depth: usize) {
+        if let Some(scalar) = self.scalar(value) {
+            lines.push(prefix + &scalar);
+            return;
+        }
+
+        let indent = INDENT.repeat(depth + 1);
+        match value {
+            YrValue::Structure(Some(members)) => {
+                lines.push(prefix + &self.paint(STYLE_KIND, "structure"));
+                for (name, member) in members {
+                    self.write_value(lines, format!("{}{}: ", indent, name), member, depth + 1);
+                }
+            }
+            YrValue::Array(items) => {
+                lines.push(prefix + &self.paint(STYLE_KIND, &format!("array[{}]", items.len())));
+                let shown = self.shown_items(items.len());
+                for (i, item) in items.iter().take(shown).enumerate() {
+                    self.write_value(lines, format!("{}[{}]: ", indent, i), item, depth + 1);
+                }
+                self.write_omitted(lines, depth + 1, items.len() - shown);
+            }
+            YrValue::Dictionary(items) => {
+                let kind = format!("dictionary[{}]", items.len());
+                lines.push(prefix + &self.paint(STYLE_KIND, &kind));
+                for (key, item) in items {
+                    let key = YrValue::escape_bytes(key.as_bytes());
+                    self.write_value(lines, format!("{}\"{}\": ", indent, key), item, depth + 1);
+                }
+            }
+            // Scalars and undefined values are rendered above
+            _ => {}
+        }
+    }
+
+    fn write_node(&self, lines: &mut Vec<String>, prefix: String, node: &ModuleNode, depth: usize) {
+        let rendered = match node.kind {
+            ObjectKind::Integer | ObjectKind::Float | ObjectKind::String => match &node.value {
+                Some(value) => self.scalar(value).unwrap_or_default(),
+                None => self.paint(STYLE_UNDEFINED, "undefined"),
+            },
+            ObjectKind::Function => {
+                let prototypes: Vec<String> = node
+                    .prototypes
+                    .iter()
+                    .map(|prototype| format!("({})", prototype))
+                    .collect();
+                let ret = node
+                    .return_kind
+                    .map_or("undefined".to_string(), |kind| kind.to_string());
+                let text = format!("function {} -> {}", prototypes.join(" | "), ret);
+                self.paint(STYLE_KIND, &text)
+            }
+            ObjectKind::Reference => match &node.reference {
+                Some(reference) => self.paint(STYLE_KIND, &format!("reference to {}", reference)),
+                None => self.paint(STYLE_UNDEFINED, "undefined"),
+            },
+            ObjectKind::Structure => self.paint(STYLE_KIND, "structure"),
+            ObjectKind::Array | ObjectKind::Dictionary => {
+                let text = format!("{}[{}]", node.kind, node.children.len());
+                self.paint(STYLE_KIND, &text)
+            }
+        };
+        lines.push(prefix + &rendered);
+
+        let indent = INDENT.repeat(depth + 1);
+        let shown = match node.kind {
+            ObjectKind::Array => self.shown_items(node.children.len()),
+            _ => node.children.len(),
+        };
+        for child in node.children.iter().take(shown) {
+            let label = match node.kind {
+                ObjectKind::Dictionary => {
+                    format!("\"{}\"", YrValue::escape_bytes(child.name.as_bytes()))
+                }
+                _ => child.name.clone(),
+            };
+            self.write_node(lines, format!("{}{}: ", indent, label), child, depth + 1);
+        }
+        self.write_omitted(lines, depth + 1, node.children.len() - shown);
+    }
+}
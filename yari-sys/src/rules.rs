@@ -0,0 +1,366 @@
+use crate::bindings::META_TYPE_BOOLEAN;
+use crate::bindings::META_TYPE_INTEGER;
+use crate::bindings::YR_MATCH;
+use crate::bindings::YR_META;
+use crate::bindings::YR_RULE;
+use crate::bindings::YR_STRING;
+use crate::bindings::YR_UNDEFINED;
+use crate::YrStringIterator;
+use crate::YrValue;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+/// Maximum depth of nested includes, same as the limit of the YARA compiler.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Source of YARA rules compiled into the context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSource {
+    /// Namespace of the rules, `None` for the default namespace
+    pub namespace: Option<String>,
+    /// Path of the file the rules were loaded from, includes are resolved relative to it
+    pub path: Option<PathBuf>,
+    pub source: String,
+}
+
+impl RuleSource {
+    /// Name of the namespace as used by YARA.
+    ///
+    /// ```
+    /// # use yari_sys::rules::RuleSource;
+    /// let source = RuleSource { namespace: None, path: None, source: String::new() };
+    /// assert_eq!(source.namespace_name(), "default");
+    /// ```
+    pub fn namespace_name(&self) -> &str {
+        self.namespace.as_deref().unwrap_or("default")
+    }
+
+    /// Source with the included files inlined in place of the `include` directives.
+    ///
+    /// Includes are resolved the same way as by the compiler. Directives of the files that
+    /// can not be read are kept, so the compiler reports them.
+    ///
+    /// ```
+    /// # use yari_sys::rules::RuleSource;
+    /// let source = RuleSource {
+    ///     namespace: None,
+    ///     path: None,
+    ///     source: "include \"does_not_exist.yar\"\nrule r { condition: true }".to_string(),
+    /// };
+    /// assert_eq!(source.expanded_source(), source.source);
+    /// ```
+    pub fn expanded_source(&self) -> String {
+        expand_includes(&self.source, self.path.as_deref(), 0)
+    }
+}
+
+/// Path of the file included as `include_name` from the `calling_file`.
+///
+/// Includes are relative to the directory of the calling file, or to the working directory
+/// for rules which were not loaded from a file.
+pub(crate) fn include_path(calling_file: Option<&Path>, include_name: &str) -> PathBuf {
+    match calling_file.and_then(|f| f.parent()) {
+        Some(dir) => dir.join(include_name),
+        None => PathBuf::from(include_name),
+    }
+}
+
+fn expand_includes(source: &str, path: Option<&Path>, depth: usize) -> String {
+    lazy_static! {
+        static ref INCLUDE_RE: Regex =
+            Regex::new(r#"(?m)^[ \t]*include[ \t]+"(?P<name>[^"]+)"[ \t]*$"#).unwrap();
+    }
+
+    if depth >= MAX_INCLUDE_DEPTH {
+        return source.to_string();
+    }
+
+    INCLUDE_RE
+        .replace_all(source, |caps: &regex::Captures| {
+            let included = include_path(path, &caps["name"]);
+            match std::fs::read_to_string(&included) {
+                Ok(included_source) => {
+                    expand_includes(&included_source, Some(&included), depth + 1)
+                }
+                Err(_) => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Value of a rule metadata entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaValue {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+}
+
+impl fmt::Display for MetaValue {
+    /// Format the value as written in the rule source.
+    ///
+    /// ```
+    /// # use yari_sys::rules::MetaValue;
+    /// assert_eq!(MetaValue::String("test".to_string()).to_string(), "\"test\"");
+    /// assert_eq!(MetaValue::Boolean(true).to_string(), "true");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetaValue::Integer(i) => write!(f, "{}", i),
+            MetaValue::String(s) => write!(f, "\"{}\"", s.escape_default()),
+            MetaValue::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// String declared in a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleString {
+    /// Identifier including the `$`, anonymous strings are named by their order (`$[1]`)
+    pub identifier: String,
+    /// Modifiers of the string (e.g. `nocase`, `wide`)
+    pub modifiers: Vec<String>,
+    /// Number of matches in the sample
+    pub matches: usize,
+}
+
+/// Match of a string in the sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringMatch {
+    /// Base address of the memory block with the match
+    pub base: i64,
+    pub offset: i64,
+    pub length: usize,
+    /// Matched data, truncated to `YR_CONFIG_MAX_MATCH_DATA` bytes
+    pub data: Vec<u8>,
+    /// Key used to decode the match of a `xor` string, `None` for other strings
+    pub xor_key: Option<u8>,
+    /// Number of the chained parts of the match, zero for strings which are not chained
+    pub chain_length: usize,
+}
+
+impl StringMatch {
+    /// Create match from the `YR_MATCH` of the `string`.
+    pub(crate) unsafe fn from_yr_match(m: &YR_MATCH, string: &YR_STRING) -> StringMatch {
+        let data = if m.data.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(m.data, m.data_length.max(0) as usize).to_vec()
+        };
+
+        // The first byte of the match is the first byte of the string xor-ed with the key
+        let plain = string.__bindgen_anon_1.string;
+        let xor_key =
+            if string.flags & STRING_FLAGS_XOR != 0 && !plain.is_null() && string.length > 0 {
+                data.first().map(|b| b ^ *plain)
+            } else {
+                None
+            };
+
+        StringMatch {
+            base: m.base,
+            offset: m.offset,
+            length: m.match_length.max(0) as usize,
+            data,
+            xor_key,
+            chain_length: m.chain_length.max(0) as usize,
+        }
+    }
+}
+
+impl From<&StringMatch> for YrValue {
+    /// Match as a structure, undefined `xor_key` is used for the strings without `xor`.
+    fn from(m: &StringMatch) -> Self {
+        YrValue::Structure(Some(IndexMap::from([
+            ("base".to_string(), YrValue::Integer(m.base)),
+            ("offset".to_string(), YrValue::Integer(m.offset)),
+            ("length".to_string(), YrValue::Integer(m.length as i64)),
+            ("data".to_string(), YrValue::from_bytes(m.data.clone())),
+            (
+                "xor_key".to_string(),
+                YrValue::Integer(m.xor_key.map_or(YR_UNDEFINED, i64::from)),
+            ),
+            (
+                "chain_length".to_string(),
+                YrValue::Integer(m.chain_length as i64),
+            ),
+        ])))
+    }
+}
+
+/// Rule compiled into the context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub namespace: String,
+    pub is_private: bool,
+    pub is_global: bool,
+    pub tags: Vec<String>,
+    /// Metadata in the order of declaration
+    pub metas: Vec<(String, MetaValue)>,
+    pub strings: Vec<RuleString>,
+}
+
+impl Rule {
+    /// Name of the rule qualified by the namespace (`namespace:rule`).
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.namespace, self.name)
+    }
+}
+
+const RULE_FLAGS_PRIVATE: i32 = 0x01;
+const RULE_FLAGS_GLOBAL: i32 = 0x02;
+
+const META_FLAGS_LAST_IN_RULE: i32 = 0x01;
+
+const STRING_FLAGS_NO_CASE: u32 = 0x04;
+const STRING_FLAGS_ASCII: u32 = 0x08;
+const STRING_FLAGS_WIDE: u32 = 0x10;
+const STRING_FLAGS_FULL_WORD: u32 = 0x80;
+const STRING_FLAGS_CHAIN_PART: u32 = 0x2000;
+const STRING_FLAGS_XOR: u32 = 0x80000;
+const STRING_FLAGS_PRIVATE: u32 = 0x100000;
+const STRING_FLAGS_BASE64: u32 = 0x200000;
+const STRING_FLAGS_BASE64_WIDE: u32 = 0x400000;
+
+/// Check if `string` is a part of the chained string other than the first one.
+///
+/// Strings with large jumps are split to chained parts, YARA refers to the string by the first
+/// part.
+pub(crate) unsafe fn is_chain_continuation(string: *const YR_STRING) -> bool {
+    (*string).flags & STRING_FLAGS_CHAIN_PART != 0
+        && !(*string).__bindgen_anon_2.chained_to.is_null()
+}
+
+/// Name of the compiled `rule` qualified by its namespace (`namespace:rule`).
+pub(crate) unsafe fn qualified_name(rule: &YR_RULE) -> String {
+    format!(
+        "{}:{}",
+        string_from_ptr((*rule.__bindgen_anon_5.ns).__bindgen_anon_1.name),
+        string_from_ptr(rule.__bindgen_anon_1.identifier)
+    )
+}
+
+/// Strings declared in the compiled `rule` with their identifiers.
+///
+/// Anonymous strings are named by their order (`$[1]`, `$[2]`, ...), chained parts other than the
+/// first one are skipped.
+pub(crate) unsafe fn string_identifiers(rule: &YR_RULE) -> Vec<(*const YR_STRING, String)> {
+    let mut anonymous = 0;
+    YrStringIterator::new(rule.__bindgen_anon_4.strings)
+        .filter(|s| !is_chain_continuation(*s))
+        .map(|s| {
+            let mut identifier = string_from_ptr((*s).__bindgen_anon_3.identifier);
+            if identifier == "$" {
+                anonymous += 1;
+                identifier = format!("$[{}]", anonymous);
+            }
+            (s, identifier)
+        })
+        .collect()
+}
+
+/// The `string` followed by its chained parts, if there are any.
+pub(crate) unsafe fn chain_parts(string: *const YR_STRING) -> Vec<*const YR_STRING> {
+    YrStringIterator::new(string.cast_mut())
+        .enumerate()
+        .take_while(|(i, s)| *i == 0 || is_chain_continuation(*s))
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// Convert C string to an owned `String`, null is converted to an empty string.
+unsafe fn string_from_ptr(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+impl Rule {
+    /// Create rule from the compiled `rule`, `matches` returns number of matches of a string.
+    pub(crate) unsafe fn from_yr_rule<F: Fn(*const YR_STRING) -> usize>(
+        rule: &YR_RULE,
+        matches: F,
+    ) -> Rule {
+        // Tags are stored as a sequence of null-terminated strings ended by an empty string
+        let mut tags = Vec::new();
+        let mut tag = rule.__bindgen_anon_2.tags;
+        while !tag.is_null() && *tag != 0 {
+            let tag_str = CStr::from_ptr(tag);
+            tags.push(tag_str.to_string_lossy().into_owned());
+            tag = tag.add(tag_str.to_bytes().len() + 1);
+        }
+
+        let mut metas = Vec::new();
+        let mut meta = rule.__bindgen_anon_3.metas as *const YR_META;
+        while !meta.is_null() {
+            let value = match (*meta).type_ as u32 {
+                META_TYPE_INTEGER => MetaValue::Integer((*meta).integer),
+                META_TYPE_BOOLEAN => MetaValue::Boolean((*meta).integer != 0),
+                _ => MetaValue::String(string_from_ptr((*meta).__bindgen_anon_2.string)),
+            };
+            metas.push((string_from_ptr((*meta).__bindgen_anon_1.identifier), value));
+
+            meta = if (*meta).flags & META_FLAGS_LAST_IN_RULE != 0 {
+                ptr::null()
+            } else {
+                meta.offset(1)
+            };
+        }
+
+        let strings = string_identifiers(rule)
+            .into_iter()
+            .map(|(s, identifier)| RuleString {
+                identifier,
+                modifiers: string_modifiers((*s).flags),
+                matches: matches(s),
+            })
+            .collect();
+
+        Rule {
+            name: string_from_ptr(rule.__bindgen_anon_1.identifier),
+            namespace: string_from_ptr((*rule.__bindgen_anon_5.ns).__bindgen_anon_1.name),
+            is_private: rule.flags & RULE_FLAGS_PRIVATE != 0,
+            is_global: rule.flags & RULE_FLAGS_GLOBAL != 0,
+            tags,
+            metas,
+            strings,
+        }
+    }
+}
+
+/// Names of the modifiers set in string `flags`.
+///
+/// YARA marks every string without `wide` as `ascii`, so `ascii` is reported only together with
+/// `wide`.
+fn string_modifiers(flags: u32) -> Vec<String> {
+    let mut modifiers = Vec::new();
+    if flags & STRING_FLAGS_WIDE != 0 {
+        if flags & STRING_FLAGS_ASCII != 0 {
+            modifiers.push("ascii".to_string());
+        }
+        modifiers.push("wide".to_string());
+    }
+
+    for (flag, name) in [
+        (STRING_FLAGS_NO_CASE, "nocase"),
+        (STRING_FLAGS_FULL_WORD, "fullword"),
+        (STRING_FLAGS_PRIVATE, "private"),
+        (STRING_FLAGS_XOR, "xor"),
+        (STRING_FLAGS_BASE64, "base64"),
+        (STRING_FLAGS_BASE64_WIDE, "base64wide"),
+    ] {
+        if flags & flag != 0 {
+            modifiers.push(name.to_string());
+        }
+    }
+    modifiers
+}
@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+/// Input scanned by the context.
+///
+/// Sample is either a file mapped from the disk or an owned in-memory buffer.
+///
+/// ```
+/// # use std::path::PathBuf;
+/// # use yari_sys::Sample;
+/// assert_eq!(Sample::from("/bin/sh"), Sample::File(PathBuf::from("/bin/sh")));
+/// assert_eq!(Sample::from(vec![0x4d, 0x5a]), Sample::Bytes(vec![0x4d, 0x5a]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sample {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl Default for Sample {
+    /// Empty sample, the same as scanning `/dev/null`.
+    fn default() -> Self {
+        Sample::File(PathBuf::from("/dev/null"))
+    }
+}
+
+impl From<PathBuf> for Sample {
+    fn from(path: PathBuf) -> Self {
+        Sample::File(path)
+    }
+}
+
+impl From<&Path> for Sample {
+    fn from(path: &Path) -> Self {
+        Sample::File(path.to_owned())
+    }
+}
+
+impl From<&str> for Sample {
+    fn from(path: &str) -> Self {
+        Sample::File(PathBuf::from(path))
+    }
+}
+
+impl From<String> for Sample {
+    fn from(path: String) -> Self {
+        Sample::File(PathBuf::from(path))
+    }
+}
+
+impl From<Vec<u8>> for Sample {
+    fn from(bytes: Vec<u8>) -> Self {
+        Sample::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Sample {
+    fn from(bytes: &[u8]) -> Self {
+        Sample::Bytes(bytes.to_vec())
+    }
+}
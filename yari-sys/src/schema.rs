@@ -0,0 +1,114 @@
+use crate::bindings::YR_OBJECT;
+use crate::bindings::YR_OBJECT_ARRAY;
+use crate::bindings::YR_OBJECT_DICTIONARY;
+use crate::bindings::YR_OBJECT_FUNCTION;
+use crate::bindings::YR_OBJECT_STRUCTURE;
+use crate::dump::ObjectKind;
+use log::error;
+use serde::Serialize;
+use std::ffi::CStr;
+
+/// Declaration of a module symbol, independent of the values set for a sample.
+///
+/// Serialized with all the fields, missing declarations are `null`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Schema {
+    pub name: String,
+    pub kind: ObjectKind,
+    /// Declaration of the items of arrays and dictionaries
+    pub item: Option<Box<Schema>>,
+    /// Argument formats of the function overloads (e.g. `"si"`)
+    pub prototypes: Vec<String>,
+    /// Kind of the value returned by the function
+    #[serde(rename = "return_type")]
+    pub return_kind: Option<ObjectKind>,
+    /// Members of the structure in the declaration order
+    pub members: Vec<Schema>,
+}
+
+impl Schema {
+    /// Walk the declaration tree of the `object`, `None` if the object type is unknown.
+    ///
+    /// Items of arrays and dictionaries are described by their prototype, so the schema is
+    /// complete even if the module did not set any item.
+    ///
+    /// # Safety
+    /// Caller must ensure that the object is valid.
+    pub(crate) unsafe fn from_object(object: *const YR_OBJECT) -> Option<Schema> {
+        let name = if (*object).identifier.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr((*object).identifier)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let Some(kind) = ObjectKind::from_object_type((*object).type_) else {
+            error!("Object {:?} has unknown type {}", name, (*object).type_);
+            return None;
+        };
+
+        let mut schema = Schema {
+            name,
+            kind,
+            item: None,
+            prototypes: Vec::new(),
+            return_kind: None,
+            members: Vec::new(),
+        };
+
+        match kind {
+            ObjectKind::Array => {
+                let item = (*object.cast::<YR_OBJECT_ARRAY>()).prototype_item;
+                schema.item = Schema::item_schema(item);
+            }
+            ObjectKind::Dictionary => {
+                let item = (*object.cast::<YR_OBJECT_DICTIONARY>()).prototype_item;
+                schema.item = Schema::item_schema(item);
+            }
+            ObjectKind::Function => {
+                let function = &*object.cast::<YR_OBJECT_FUNCTION>();
+                schema.return_kind = function
+                    .return_obj
+                    .as_ref()
+                    .and_then(|ret| ObjectKind::from_object_type(ret.type_));
+                schema.prototypes = function
+                    .prototypes
+                    .iter()
+                    .take_while(|prototype| !prototype.arguments_fmt.is_null())
+                    .map(|prototype| {
+                        CStr::from_ptr(prototype.arguments_fmt)
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect();
+            }
+            ObjectKind::Structure => {
+                let structure = &*object.cast::<YR_OBJECT_STRUCTURE>();
+                schema.members = structure
+                    .declared_members()
+                    .into_iter()
+                    .filter_map(|member| Schema::from_object(member))
+                    .collect();
+            }
+            ObjectKind::Integer
+            | ObjectKind::Float
+            | ObjectKind::String
+            | ObjectKind::Reference => {}
+        }
+
+        Some(schema)
+    }
+
+    unsafe fn item_schema(item: *const YR_OBJECT) -> Option<Box<Schema>> {
+        if item.is_null() {
+            None
+        } else {
+            Schema::from_object(item).map(Box::new)
+        }
+    }
+
+    /// Member of the structure with the `name`.
+    pub fn member(&self, name: &str) -> Option<&Schema> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
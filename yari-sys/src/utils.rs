@@ -5,22 +5,76 @@ use regex::Regex;
 
 use crate::error::YariError;
 
+lazy_static! {
+    static ref RULE_RE: Regex =
+        Regex::new(r"(?m)^(private )?rule (?P<name>\w+)(.|\n)+?condition:(.|\n)+?}$").unwrap();
+}
+
+/// Check if `rules` contain a definition of the rule `rule_name`.
+///
+/// ```
+/// # use yari_sys::utils::rules_contain_rule;
+/// assert!(rules_contain_rule("rule test { condition: true }", "test"));
+/// assert!(!rules_contain_rule("rule test { condition: true }", "other"));
+/// ```
+pub fn rules_contain_rule(rules: &str, rule_name: &str) -> bool {
+    RULE_RE
+        .captures_iter(rules)
+        .any(|caps| caps.name("name").map(|m| m.as_str()) == Some(rule_name))
+}
+
+/// Condition of the rule `rule_name` defined in `rules`.
+///
+/// ```
+/// # use yari_sys::utils::rule_condition;
+/// let rules = "rule test {\n    condition:\n        true and\n        false\n}";
+/// assert_eq!(rule_condition(rules, "test"), Some("true and\n        false"));
+/// assert_eq!(rule_condition(rules, "other"), None);
+/// ```
+pub fn rule_condition<'a>(rules: &'a str, rule_name: &str) -> Option<&'a str> {
+    let rule = RULE_RE
+        .captures_iter(rules)
+        .find(|caps| caps.name("name").map(|m| m.as_str()) == Some(rule_name))?
+        .get(0)?
+        .as_str();
+
+    let start = rule.find("condition:")? + "condition:".len();
+    let end = rule.rfind('}')?;
+    Some(rule[start..end].trim())
+}
+
+/// Names of the modules imported by the `rules`.
+///
+/// ```
+/// # use yari_sys::utils::rules_imports;
+/// let rules = "import \"pe\"\nimport \"math\"\nrule r { condition: true }";
+/// assert_eq!(rules_imports(rules), vec!["pe", "math"]);
+/// ```
+pub fn rules_imports(rules: &str) -> Vec<String> {
+    lazy_static! {
+        static ref IMPORT_RE: Regex = Regex::new(r#"(?m)^\s*import\s+"(?P<name>[^"]+)""#).unwrap();
+    }
+
+    IMPORT_RE
+        .captures_iter(rules)
+        .filter_map(|caps| caps.name("name").map(|m| m.as_str().to_string()))
+        .collect()
+}
+
 pub fn expression_to_rules_with_condition(
     rules: &str,
     target_rule: &str,
     expression: &str,
 ) -> Result<String, YariError> {
     lazy_static! {
-        static ref IMPORT_RE: Regex = Regex::new(r#"(?m)^import "\w+"$"#).unwrap();
-        static ref RULE_RE: Regex =
-            Regex::new(r"(?m)^(private )?rule (?P<name>\w+)(.|\n)+?condition:(.|\n)+?}$").unwrap();
+        static ref IMPORT_RE: Regex = Regex::new(r#"(?m)^(import|include) "[^"]+"$"#).unwrap();
         static ref STRINGS_RE: Regex = Regex::new(r"\s+strings:\s+\$").unwrap();
         static ref CONDITION_RE: Regex = Regex::new("condition:(.|\n)+}\n").unwrap();
     }
 
     let mut new_rules = String::new();
 
-    // Preserve imports from original rules
+    // Preserve imports and includes from original rules
     for caps in IMPORT_RE.captures_iter(rules) {
         new_rules.push_str(caps.get(0).ok_or(YariError::EvalError)?.as_str());
         new_rules.push('\n');
@@ -45,6 +99,49 @@ pub fn expression_to_rules_with_condition(
     Ok(new_rules)
 }
 
+/// Process escape sequences of a YARA string literal.
+///
+/// Supported sequences are the same as in YARA text strings: `\"`, `\\`, `\t`, `\n`, `\r` and
+/// `\xHH`. Sequence `\'` is accepted as well, because it is produced when escaping string
+/// values of YARA objects.
+///
+/// ```
+/// # use yari_sys::utils::unescape_string;
+/// assert_eq!(unescape_string(r"a\x41\n"), Ok(b"aA\n".to_vec()));
+/// assert!(unescape_string(r"\q").is_err());
+/// ```
+pub fn unescape_string(string: &str) -> Result<Vec<u8>, YariError> {
+    let mut res = Vec::with_capacity(string.len());
+    let mut bytes = string.bytes();
+
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            res.push(b);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(b'"') => res.push(b'"'),
+            Some(b'\'') => res.push(b'\''),
+            Some(b'\\') => res.push(b'\\'),
+            Some(b't') => res.push(b'\t'),
+            Some(b'n') => res.push(b'\n'),
+            Some(b'r') => res.push(b'\r'),
+            Some(b'x') => {
+                let hex = [
+                    bytes.next().ok_or(YariError::ParserError)?,
+                    bytes.next().ok_or(YariError::ParserError)?,
+                ];
+                let hex = std::str::from_utf8(&hex).map_err(|_| YariError::ParserError)?;
+                res.push(u8::from_str_radix(hex, 16).map_err(|_| YariError::ParserError)?);
+            }
+            _ => return Err(YariError::ParserError),
+        }
+    }
+
+    Ok(res)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +203,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expression_to_condition_with_include() {
+        let res = expression_to_rules_with_condition(
+            "import \"pe\"\ninclude \"../common.yar\"\nrule test { condition: true }",
+            "test",
+            "common",
+        );
+        assert_eq!(
+            res,
+            Ok(String::from(
+                "import \"pe\"\ninclude \"../common.yar\"\nrule test { condition: (common) }\n"
+            ))
+        );
+    }
+
     #[test]
     fn test_expression_to_condition_oneline_with_string() {
         let res = expression_to_rules_with_condition(
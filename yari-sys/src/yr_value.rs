@@ -11,7 +11,12 @@ use crate::bindings::YR_OBJECT_ARRAY;
 use crate::bindings::YR_OBJECT_STRUCTURE;
 use crate::bindings::YR_UNDEFINED;
 use crate::error::YariError;
-use std::collections::HashMap;
+use crate::parser::Argument;
+use crate::parser::BinaryOperator;
+use crate::parser::UnaryOperator;
+use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
 use std::ffi::CStr;
 
 #[cfg(feature = "avast")]
@@ -21,14 +26,19 @@ use crate::bindings::OBJECT_TYPE_REFERENCE;
 use crate::bindings::YR_OBJECT_REFERENCE;
 
 /// Result of evalutaion.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum YrValue {
     Integer(i64),
     Float(f64),
+    /// Valid UTF-8 string, `None` if the string is undefined
     String(Option<String>),
-    Dictionary(HashMap<String, YrValue>),
+    /// String which is not valid UTF-8 (e.g. binary data of the module)
+    Bytes(Vec<u8>),
+    /// Items in the order they were inserted by the module
+    Dictionary(IndexMap<String, YrValue>),
     Array(Vec<YrValue>),
-    Structure(Option<HashMap<String, YrValue>>),
+    /// Members in the order they were declared by the module, `None` if undefined
+    Structure(Option<IndexMap<String, YrValue>>),
 }
 
 impl YrValue {
@@ -36,7 +46,7 @@ impl YrValue {
     ///
     /// ```rust
     /// # use yari_sys::YrValue;
-    /// use std::collections::HashMap;
+    /// use indexmap::IndexMap;
     /// use yari_sys::YR_UNDEFINED;
     ///
     /// assert!(!YrValue::Integer(1).is_undefined());
@@ -50,11 +60,12 @@ impl YrValue {
     /// assert!(!YrValue::String(Some("not empty".to_string())).is_undefined());
     /// assert!(!YrValue::String(Some("".to_string())).is_undefined());
     /// assert!(YrValue::String(None).is_undefined());
+    /// assert!(!YrValue::Bytes(vec![0xff]).is_undefined());
     ///
-    /// assert!(!YrValue::Dictionary(HashMap::new()).is_undefined());
+    /// assert!(!YrValue::Dictionary(IndexMap::new()).is_undefined());
     /// assert!(!YrValue::Array(Vec::new()).is_undefined());
     ///
-    /// assert!(!YrValue::Structure(Some(HashMap::new())).is_undefined());
+    /// assert!(!YrValue::Structure(Some(IndexMap::new())).is_undefined());
     /// assert!(YrValue::Structure(None).is_undefined());
     /// ```
     pub fn is_undefined(&self) -> bool {
@@ -62,22 +73,81 @@ impl YrValue {
             YrValue::Integer(i) => *i == YR_UNDEFINED,
             YrValue::Float(f) => f.is_nan(),
             YrValue::String(s) => s.is_none(),
+            YrValue::Bytes(_) => false,
             YrValue::Dictionary(_) => false,
             YrValue::Array(_) => false,
             YrValue::Structure(s) => s.is_none(),
         }
     }
 
-    fn sized_string_to_string(ss: *const SIZED_STRING) -> String {
-        let string_slice_i8 = unsafe { (*ss).c_string.as_slice((*ss).length as usize) };
-        let string_slice_u8 = unsafe { &*(string_slice_i8 as *const _ as *const [u8]) };
-        string_slice_u8
+    /// Integer value used as an operand, `None` if the value is undefined.
+    pub(crate) fn as_integer(&self) -> Result<Option<i64>, YariError> {
+        match self {
+            _ if self.is_undefined() => Ok(None),
+            YrValue::Integer(i) => Ok(Some(*i)),
+            _ => Err(YariError::EvalError),
+        }
+    }
+
+    /// String value of the `bytes`, `Bytes` is used only if they are not valid UTF-8.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// assert_eq!(YrValue::from_bytes(b"MZ".to_vec()), YrValue::String(Some("MZ".to_string())));
+    /// assert_eq!(YrValue::from_bytes(b"M\xff".to_vec()), YrValue::Bytes(b"M\xff".to_vec()));
+    /// ```
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(s) => YrValue::String(Some(s)),
+            Err(e) => YrValue::Bytes(e.into_bytes()),
+        }
+    }
+
+    /// Raw content of the string value, `None` for other values and undefined strings.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// assert_eq!(YrValue::String(Some("MZ".to_string())).as_bytes(), Some(&b"MZ"[..]));
+    /// assert_eq!(YrValue::Bytes(vec![0xff]).as_bytes(), Some(&[0xff][..]));
+    /// assert_eq!(YrValue::String(None).as_bytes(), None);
+    /// assert_eq!(YrValue::Integer(1).as_bytes(), None);
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            YrValue::String(Some(s)) => Some(s.as_bytes()),
+            YrValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Escape `bytes` for printing, non-printable bytes are shown as `\xNN`.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// assert_eq!(YrValue::escape_bytes(b"Dan\x00\n\"S\""), r#"Dan\x00\n\"S\""#);
+    /// ```
+    pub fn escape_bytes(bytes: &[u8]) -> String {
+        bytes
             .iter()
             .map(|c| std::ascii::escape_default(*c).to_string())
             .collect::<Vec<_>>()
             .join("")
     }
 
+    /// Replace the string values by their escaped form as used in YARA string literals.
+    pub(crate) fn into_escaped(self) -> Self {
+        match self.as_bytes() {
+            Some(bytes) => YrValue::String(Some(YrValue::escape_bytes(bytes))),
+            None => self,
+        }
+    }
+
+    fn sized_string_to_bytes(ss: *const SIZED_STRING) -> Vec<u8> {
+        let string_slice_i8 = unsafe { (*ss).c_string.as_slice((*ss).length as usize) };
+        let string_slice_u8 = unsafe { &*(string_slice_i8 as *const _ as *const [u8]) };
+        string_slice_u8.to_vec()
+    }
+
     /// # Safety
     /// Caller must ensure that the block is a valid.
     unsafe fn from_inner(object: *const YR_OBJECT, _include_references: bool) -> Self {
@@ -87,18 +157,19 @@ impl YrValue {
                 if sized_string_ptr.is_null() {
                     YrValue::String(None)
                 } else {
-                    let owned_string = YrValue::sized_string_to_string((*object).value.ss);
-                    YrValue::String(Some(owned_string))
+                    YrValue::from_bytes(YrValue::sized_string_to_bytes(sized_string_ptr))
                 }
             }
             OBJECT_TYPE_FLOAT => YrValue::Float((*object).value.d),
             OBJECT_TYPE_INTEGER => YrValue::Integer((*object).value.i),
             OBJECT_TYPE_DICTIONARY => {
-                let mut map = HashMap::new();
+                let mut map = IndexMap::new();
                 let iter = YR_DICT_ITERATOR::new(object as *mut YR_OBJECT);
 
                 for (key, obj_ptr) in iter {
-                    let key_string = YrValue::sized_string_to_string(key);
+                    // Keys which are not valid UTF-8 are kept escaped
+                    let key_string = String::from_utf8(YrValue::sized_string_to_bytes(key))
+                        .unwrap_or_else(|e| YrValue::escape_bytes(e.as_bytes()));
                     map.insert(
                         key_string,
                         YrValue::from_inner(obj_ptr, _include_references),
@@ -122,10 +193,10 @@ impl YrValue {
                 YrValue::Array(vec)
             }
             OBJECT_TYPE_STRUCTURE => {
-                let mut map = HashMap::new();
+                let mut map = IndexMap::new();
                 let structure = object.cast::<YR_OBJECT_STRUCTURE>();
 
-                for obj in (*structure).members() {
+                for obj in (*structure).declared_members() {
                     let key_string = CStr::from_ptr((*obj).identifier)
                         .to_str()
                         .unwrap()
@@ -152,6 +223,239 @@ impl YrValue {
     pub(crate) unsafe fn from(object: *const YR_OBJECT) -> Self {
         YrValue::from_inner(object, true)
     }
+
+    fn undefined() -> Self {
+        YrValue::Integer(YR_UNDEFINED)
+    }
+
+    fn from_bool(value: bool) -> Self {
+        YrValue::Integer(value as i64)
+    }
+
+    /// Apply the unary `operator` following the YARA semantics.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// use yari_sys::parser::UnaryOperator;
+    /// use yari_sys::YR_UNDEFINED;
+    ///
+    /// assert_eq!(YrValue::Integer(3).unary_operation(UnaryOperator::Minus), Ok(YrValue::Integer(-3)));
+    /// assert_eq!(YrValue::Integer(0).unary_operation(UnaryOperator::Not), Ok(YrValue::Integer(1)));
+    /// assert!(YrValue::Integer(YR_UNDEFINED).unary_operation(UnaryOperator::Not).unwrap().is_undefined());
+    /// assert_eq!(YrValue::Integer(YR_UNDEFINED).unary_operation(UnaryOperator::Defined), Ok(YrValue::Integer(0)));
+    /// ```
+    pub fn unary_operation(self, operator: UnaryOperator) -> Result<YrValue, YariError> {
+        if operator == UnaryOperator::Defined {
+            return Ok(YrValue::from_bool(!self.is_undefined()));
+        }
+
+        if self.is_undefined() {
+            return Ok(YrValue::undefined());
+        }
+
+        match (operator, self) {
+            (UnaryOperator::Not, value) => Ok(YrValue::from_bool(!bool::try_from(value)?)),
+            (UnaryOperator::Minus, YrValue::Integer(i)) => Ok(YrValue::Integer(i.wrapping_neg())),
+            (UnaryOperator::Minus, YrValue::Float(f)) => Ok(YrValue::Float(-f)),
+            (UnaryOperator::BitwiseNot, YrValue::Integer(i)) => Ok(YrValue::Integer(!i)),
+            _ => Err(YariError::EvalError),
+        }
+    }
+
+    /// Apply the binary `operator` to `self` and `rhs` following the YARA semantics.
+    ///
+    /// Undefined operands make the result undefined, with the exception of `and` and `or` which
+    /// treat undefined values as `false`. Booleans are represented as `Integer(0)` and
+    /// `Integer(1)`.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// use yari_sys::parser::BinaryOperator;
+    /// use yari_sys::YR_UNDEFINED;
+    ///
+    /// let add = YrValue::Integer(1).binary_operation(BinaryOperator::Add, YrValue::Float(0.5));
+    /// assert_eq!(add, Ok(YrValue::Float(1.5)));
+    ///
+    /// let div = YrValue::Integer(1).binary_operation(BinaryOperator::Div, YrValue::Integer(0));
+    /// assert!(div.unwrap().is_undefined());
+    ///
+    /// let or = YrValue::Integer(YR_UNDEFINED).binary_operation(BinaryOperator::Or, YrValue::Integer(1));
+    /// assert_eq!(or, Ok(YrValue::Integer(1)));
+    ///
+    /// let contains = YrValue::String(Some(".text".to_string()))
+    ///     .binary_operation(BinaryOperator::IContains, YrValue::String(Some("TEXT".to_string())));
+    /// assert_eq!(contains, Ok(YrValue::Integer(1)));
+    /// ```
+    pub fn binary_operation(
+        self,
+        operator: BinaryOperator,
+        rhs: YrValue,
+    ) -> Result<YrValue, YariError> {
+        match operator {
+            BinaryOperator::And => {
+                return Ok(YrValue::from_bool(
+                    bool::try_from(self)? && bool::try_from(rhs)?,
+                ))
+            }
+            BinaryOperator::Or => {
+                return Ok(YrValue::from_bool(
+                    bool::try_from(self)? || bool::try_from(rhs)?,
+                ))
+            }
+            _ => {}
+        }
+
+        if self.is_undefined() || rhs.is_undefined() {
+            return Ok(YrValue::undefined());
+        }
+
+        match (self, rhs) {
+            (YrValue::Integer(lhs), YrValue::Integer(rhs)) => {
+                YrValue::integer_operation(operator, lhs, rhs)
+            }
+            (YrValue::Integer(lhs), YrValue::Float(rhs)) => {
+                YrValue::float_operation(operator, lhs as f64, rhs)
+            }
+            (YrValue::Float(lhs), YrValue::Integer(rhs)) => {
+                YrValue::float_operation(operator, lhs, rhs as f64)
+            }
+            (YrValue::Float(lhs), YrValue::Float(rhs)) => {
+                YrValue::float_operation(operator, lhs, rhs)
+            }
+            (lhs, rhs) => match (lhs.as_bytes(), rhs.as_bytes()) {
+                (Some(lhs), Some(rhs)) => YrValue::string_operation(operator, lhs, rhs),
+                _ => Err(YariError::EvalError),
+            },
+        }
+    }
+
+    fn compare(operator: BinaryOperator, ordering: Option<Ordering>) -> Option<bool> {
+        let ordering = ordering?;
+        match operator {
+            BinaryOperator::Equal => Some(ordering == Ordering::Equal),
+            BinaryOperator::NotEqual => Some(ordering != Ordering::Equal),
+            BinaryOperator::Less => Some(ordering == Ordering::Less),
+            BinaryOperator::LessEqual => Some(ordering != Ordering::Greater),
+            BinaryOperator::Greater => Some(ordering == Ordering::Greater),
+            BinaryOperator::GreaterEqual => Some(ordering != Ordering::Less),
+            _ => None,
+        }
+    }
+
+    fn integer_operation(
+        operator: BinaryOperator,
+        lhs: i64,
+        rhs: i64,
+    ) -> Result<YrValue, YariError> {
+        if let Some(res) = YrValue::compare(operator, Some(lhs.cmp(&rhs))) {
+            return Ok(YrValue::from_bool(res));
+        }
+
+        let res = match operator {
+            BinaryOperator::Add => Some(lhs.wrapping_add(rhs)),
+            BinaryOperator::Sub => Some(lhs.wrapping_sub(rhs)),
+            BinaryOperator::Mul => Some(lhs.wrapping_mul(rhs)),
+            BinaryOperator::Div => lhs.checked_div(rhs),
+            BinaryOperator::Mod => lhs.checked_rem(rhs),
+            BinaryOperator::BitwiseAnd => Some(lhs & rhs),
+            BinaryOperator::BitwiseOr => Some(lhs | rhs),
+            BinaryOperator::BitwiseXor => Some(lhs ^ rhs),
+            BinaryOperator::ShiftLeft => Some(if (0..64).contains(&rhs) {
+                lhs << rhs
+            } else {
+                0
+            }),
+            BinaryOperator::ShiftRight => Some(if (0..64).contains(&rhs) {
+                lhs >> rhs
+            } else {
+                0
+            }),
+            _ => return Err(YariError::EvalError),
+        };
+
+        Ok(res.map(YrValue::Integer).unwrap_or_else(YrValue::undefined))
+    }
+
+    fn float_operation(operator: BinaryOperator, lhs: f64, rhs: f64) -> Result<YrValue, YariError> {
+        if let Some(res) = YrValue::compare(operator, lhs.partial_cmp(&rhs)) {
+            return Ok(YrValue::from_bool(res));
+        }
+
+        match operator {
+            BinaryOperator::Add => Ok(YrValue::Float(lhs + rhs)),
+            BinaryOperator::Sub => Ok(YrValue::Float(lhs - rhs)),
+            BinaryOperator::Mul => Ok(YrValue::Float(lhs * rhs)),
+            BinaryOperator::Div => Ok(YrValue::Float(lhs / rhs)),
+            _ => Err(YariError::EvalError),
+        }
+    }
+
+    /// Strings are compared byte by byte, the same as in YARA.
+    fn string_operation(
+        operator: BinaryOperator,
+        lhs: &[u8],
+        rhs: &[u8],
+    ) -> Result<YrValue, YariError> {
+        if let Some(res) = YrValue::compare(operator, Some(lhs.cmp(rhs))) {
+            return Ok(YrValue::from_bool(res));
+        }
+
+        let lhs_lower = lhs.to_ascii_lowercase();
+        let rhs_lower = rhs.to_ascii_lowercase();
+
+        let contains = |haystack: &[u8], needle: &[u8]| {
+            needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+        };
+
+        let res = match operator {
+            BinaryOperator::Contains => contains(lhs, rhs),
+            BinaryOperator::IContains => contains(&lhs_lower, &rhs_lower),
+            BinaryOperator::StartsWith => lhs.starts_with(rhs),
+            BinaryOperator::IStartsWith => lhs_lower.starts_with(&rhs_lower),
+            BinaryOperator::EndsWith => lhs.ends_with(rhs),
+            BinaryOperator::IEndsWith => lhs_lower.ends_with(&rhs_lower),
+            BinaryOperator::IEquals => lhs_lower == rhs_lower,
+            _ => return Err(YariError::EvalError),
+        };
+
+        Ok(YrValue::from_bool(res))
+    }
+}
+
+impl Serialize for YrValue {
+    /// Serialize the value as its plain data, undefined values are `null`.
+    ///
+    /// Structures and dictionaries are maps keeping the order of their members, strings which
+    /// are not valid UTF-8 are sequences of the byte values.
+    ///
+    /// ```rust
+    /// # use yari_sys::YrValue;
+    /// use indexmap::IndexMap;
+    /// use yari_sys::YR_UNDEFINED;
+    ///
+    /// let mut section = IndexMap::new();
+    /// section.insert("name".to_string(), YrValue::String(Some(".text".to_string())));
+    /// section.insert("raw_data_size".to_string(), YrValue::Integer(YR_UNDEFINED));
+    /// let sections = YrValue::Array(vec![YrValue::Structure(Some(section)), YrValue::Bytes(vec![0x4d, 0xff])]);
+    /// assert_eq!(
+    ///     serde_json::to_string(&sections).unwrap(),
+    ///     r#"[{"name":".text","raw_data_size":null},[77,255]]"#
+    /// );
+    /// ```
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            _ if self.is_undefined() => serializer.serialize_none(),
+            YrValue::Integer(i) => serializer.serialize_i64(*i),
+            YrValue::Float(f) => serializer.serialize_f64(*f),
+            YrValue::String(s) => s.serialize(serializer),
+            YrValue::Bytes(b) => serializer.collect_seq(b),
+            YrValue::Array(items) => serializer.collect_seq(items),
+            YrValue::Dictionary(members) | YrValue::Structure(Some(members)) => {
+                serializer.collect_map(members)
+            }
+            YrValue::Structure(None) => serializer.serialize_none(),
+        }
+    }
 }
 
 impl TryFrom<YrValue> for bool {
@@ -160,7 +464,7 @@ impl TryFrom<YrValue> for bool {
     /// ```rust
     /// # use yari_sys::YrValue;
     /// use yari_sys::YR_UNDEFINED;
-    /// use std::collections::HashMap;
+    /// use indexmap::IndexMap;
     ///
     /// assert!(bool::try_from(YrValue::Integer(1)).unwrap());
     /// assert!(!bool::try_from(YrValue::Integer(0)).unwrap());
@@ -173,12 +477,13 @@ impl TryFrom<YrValue> for bool {
     /// assert!(bool::try_from(YrValue::String(Some("not empty".to_string()))).unwrap());
     /// assert!(!bool::try_from(YrValue::String(Some("".to_string()))).unwrap());
     /// assert!(!bool::try_from(YrValue::String(None)).unwrap());
+    /// assert!(bool::try_from(YrValue::Bytes(vec![0])).unwrap());
     ///
-    /// assert!(bool::try_from(YrValue::Dictionary(HashMap::new())).is_err());
+    /// assert!(bool::try_from(YrValue::Dictionary(IndexMap::new())).is_err());
     /// assert!(bool::try_from(YrValue::Array(Vec::new())).is_err());
     ///
     /// assert!(!bool::try_from(YrValue::Structure(None)).unwrap());
-    /// assert!(bool::try_from(YrValue::Structure(Some(HashMap::new()))).unwrap());
+    /// assert!(bool::try_from(YrValue::Structure(Some(IndexMap::new()))).unwrap());
     /// ```
     fn try_from(value: YrValue) -> Result<Self, Self::Error> {
         match value {
@@ -187,9 +492,34 @@ impl TryFrom<YrValue> for bool {
             YrValue::String(ref s) => {
                 Ok(!value.is_undefined() && s.as_ref().map(|s| !s.is_empty()).unwrap_or(false))
             }
+            YrValue::Bytes(ref b) => Ok(!b.is_empty()),
             YrValue::Dictionary(_) => Err(YariError::BoolConversionError),
             YrValue::Array(_) => Err(YariError::BoolConversionError),
             YrValue::Structure(_) => Ok(!value.is_undefined()),
         }
     }
 }
+
+impl<'a> TryFrom<&'a YrValue> for Argument<'a> {
+    type Error = YariError;
+
+    /// Convert evaluated value to a function argument.
+    ///
+    /// String arguments are expected in the escaped form (see `YrValue::into_escaped`), the
+    /// same as string literals.
+    ///
+    /// ```
+    /// # use yari_sys::parser::Argument;
+    /// # use yari_sys::YrValue;
+    /// assert_eq!(Argument::try_from(&YrValue::Integer(1)), Ok(Argument::Integer(1)));
+    /// assert!(Argument::try_from(&YrValue::String(None)).is_err());
+    /// ```
+    fn try_from(value: &'a YrValue) -> Result<Self, Self::Error> {
+        match value {
+            YrValue::Integer(i) => Ok(Argument::Integer(*i)),
+            YrValue::Float(f) => Ok(Argument::Float(*f)),
+            YrValue::String(Some(s)) => Ok(Argument::String(s)),
+            _ => Err(YariError::EvalError),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use yari_sys::Module;
 use yari_sys::{Context, ContextBuilder};
 
@@ -84,3 +84,224 @@ rule r {
         ),
     )
 }
+
+pub fn context_with_externals_and_rule() -> Context {
+    let _ = env_logger::builder().is_test(true).try_init();
+    ContextBuilder::default()
+        .with_external_int("int_var", 10)
+        .with_external_float("float_var", 1.5)
+        .with_external_bool("bool_var", true)
+        .with_external_string("str_var", "test")
+        .with_rule_string(Some(
+            "rule r {
+    condition:
+        int_var == 10 and bool_var and str_var == \"test\"
+}",
+        ))
+        .build()
+        .unwrap()
+}
+
+pub fn context_with_pe_sample_bytes_and_rule() -> Context {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let test_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(test_root.join("tests/assets/pe_hello_world")).unwrap();
+
+    ContextBuilder::default()
+        .with_sample_bytes(bytes)
+        .with_rule_string(Some(
+            "import \"pe\"
+rule r {
+    strings:
+        $s00 = \"Hello\"
+    condition:
+        $s00 and pe.number_of_sections == 4
+}",
+        ))
+        .build()
+        .unwrap()
+}
+
+pub const HELLO_WORLD: &[u8] = b"Hello world";
+
+/// Builder of a context scanning the `sample` bytes with the `rules` in the default namespace.
+pub fn builder_with_sample_bytes_and_rules(sample: &[u8], rules: &str) -> ContextBuilder {
+    let _ = env_logger::builder().is_test(true).try_init();
+    ContextBuilder::default()
+        .with_sample_bytes(sample.to_vec())
+        .with_rule_string(Some(rules))
+}
+
+const EXPLAIN_RULES: &str = "import \"pe\"
+
+rule r {
+    strings:
+        $s01 = \"not found\"
+    condition:
+        $s01 and
+        filesize > 5
+}
+
+rule not_pe {
+    strings:
+        $s00 = \"Hello\"
+    condition:
+        pe.number_of_sections > 2 or $s00
+}
+
+rule matching {
+    condition:
+        filesize == 11 or filesize == 0
+}";
+
+pub fn context_with_explained_rules() -> Context {
+    builder_with_sample_bytes_and_rules(HELLO_WORLD, EXPLAIN_RULES)
+        .build()
+        .unwrap()
+}
+
+const DISASM_RULES: &str = "import \"pe\"
+
+rule r {
+    strings:
+        $s00 = \"Hello\"
+    condition:
+        $s00 and filesize > 5
+}
+
+rule sections {
+    condition:
+        pe.number_of_sections == 4 and pe.sections[0].name == \".text\"
+}";
+
+pub fn context_with_disassembled_rules() -> Context {
+    builder_with_sample_bytes_and_rules(HELLO_WORLD, DISASM_RULES)
+        .with_rule_string_ns("other", "rule r { condition: true }")
+        .build()
+        .unwrap()
+}
+
+const PROFILE_RULES: &str = "rule r {
+    strings:
+        $s00 = \"o\"
+        $ = \"Hello\"
+    condition:
+        any of them
+}
+
+rule no_strings {
+    condition:
+        filesize > 0
+}";
+
+pub fn context_with_profiled_rules(profiling: bool) -> Context {
+    builder_with_sample_bytes_and_rules(HELLO_WORLD, PROFILE_RULES)
+        .with_profiling(profiling)
+        .build()
+        .unwrap()
+}
+
+const ATOM_RULES: &str = "rule r {
+    strings:
+        $s00 = \"Hello\"
+        $s01 = { 00 00 00 00 }
+        $ = \"world\"
+    condition:
+        any of them
+}
+
+rule other {
+    strings:
+        $s00 = \"other\"
+    condition:
+        $s00
+}";
+
+pub fn context_with_atom_rules(sample: &[u8]) -> Context {
+    builder_with_sample_bytes_and_rules(sample, ATOM_RULES)
+        .build()
+        .unwrap()
+}
+
+const INTROSPECTION_RULES: &str = "import \"pe\"
+import \"math\"
+
+private rule PRIVATE : tag1 tag2 {
+    meta:
+        author = \"test\"
+        version = 2
+        enabled = true
+    condition:
+        true
+}
+
+global rule GLOBAL {
+    condition:
+        true
+}
+
+rule r {
+    strings:
+        $s00 = \"Hello\" ascii wide
+        $s01 = \"world\" nocase fullword
+        $ = \"o\"
+    condition:
+        any of them
+}";
+
+pub fn context_with_introspected_rules() -> Context {
+    builder_with_sample_bytes_and_rules(HELLO_WORLD, INTROSPECTION_RULES)
+        .with_rule_string_ns("other", "rule r { condition: false }")
+        .build()
+        .unwrap()
+}
+
+pub fn context_with_string_matches() -> Context {
+    builder_with_sample_bytes_and_rules(
+        b"Hello WORLD Hello",
+        "rule r {
+    strings:
+        $s00 = \"Hello\"
+        $s01 = \"world\" xor(0x20)
+        $s02 = \"not found\"
+    condition:
+        all of them
+}",
+    )
+    .build()
+    .unwrap()
+}
+
+const COMPILED_RULES: &str = "rule r {
+    strings:
+        $s00 = \"Hello\"
+        $s01 = \"not found\"
+    condition:
+        $s00 and not $s01
+}
+
+rule not_matching {
+    condition:
+        filesize == 0
+}";
+
+/// Compile `COMPILED_RULES` and save them to a temporary file named `name`.
+pub fn compiled_rules(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("yari-{}-{}.yarc", name, std::process::id()));
+
+    builder_with_sample_bytes_and_rules(HELLO_WORLD, COMPILED_RULES)
+        .build()
+        .unwrap()
+        .save_compiled_rules(&path)
+        .unwrap();
+    path
+}
+
+pub fn context_with_compiled_rules(path: &Path) -> Context {
+    let _ = env_logger::builder().is_test(true).try_init();
+    ContextBuilder::default()
+        .with_sample_bytes(HELLO_WORLD.to_vec())
+        .with_compiled_rules(path)
+        .build()
+        .unwrap()
+}
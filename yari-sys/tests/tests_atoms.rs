@@ -0,0 +1,54 @@
+extern crate yari_sys;
+
+mod common;
+
+use yari_sys::YariError;
+
+#[test]
+fn test_atoms_strings() {
+    let context = common::context_with_atom_rules(common::HELLO_WORLD);
+    let strings = context.atoms("r").unwrap();
+
+    let identifiers: Vec<_> = strings.iter().map(|s| s.identifier.as_str()).collect();
+    assert_eq!(identifiers, vec!["$s00", "$s01", "$[1]"]);
+    assert!(strings.iter().all(|s| !s.atoms.is_empty()));
+
+    // Atoms are substrings of the string
+    for atom in &strings[0].atoms {
+        let start = atom.backtrack as usize;
+        assert_eq!(
+            &b"Hello"[start..start + atom.bytes.len()],
+            atom.bytes.as_slice()
+        );
+    }
+}
+
+#[test]
+fn test_atoms_quality() {
+    let context = common::context_with_atom_rules(common::HELLO_WORLD);
+    let strings = context.atoms("r").unwrap();
+
+    let hello = &strings[0].atoms[0];
+    let zeros = &strings[1].atoms[0];
+    assert_eq!(zeros.bytes, vec![0, 0, 0, 0]);
+    assert!(zeros.quality < hello.quality);
+    assert!(zeros.is_slow());
+}
+
+#[test]
+fn test_atoms_hits() {
+    let context = common::context_with_atom_rules(b"Hello world\x00\x00\x00\x00\x00\x00");
+    let strings = context.atoms("r").unwrap();
+
+    assert_eq!(strings[0].atoms.iter().map(|a| a.hits).sum::<u64>(), 1);
+    assert_eq!(strings[1].atoms.iter().map(|a| a.hits).sum::<u64>(), 3);
+
+    let other = context.atoms("other").unwrap();
+    assert_eq!(other[0].atoms.iter().map(|a| a.hits).sum::<u64>(), 0);
+}
+
+#[test]
+fn test_atoms_missing_rule() {
+    let context = common::context_with_atom_rules(b"");
+    assert_eq!(context.atoms("missing"), Err(YariError::RuleMissingError));
+}
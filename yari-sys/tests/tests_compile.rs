@@ -0,0 +1,87 @@
+extern crate yari_sys;
+
+mod common;
+
+use yari_sys::error::{Diagnostic, Severity};
+use yari_sys::ContextBuilder;
+use yari_sys::YariError;
+
+#[test]
+fn test_compile_error() {
+    let res = ContextBuilder::default()
+        .with_rule_string(Some(
+            "rule r {
+    condition:
+        undefined_identifier
+}",
+        ))
+        .build();
+
+    let Err(YariError::CompileError(diagnostics)) = res else {
+        panic!("expected compile error");
+    };
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].line, 3);
+    assert_eq!(diagnostics[0].file, None);
+    assert!(diagnostics[0].message.contains("undefined_identifier"));
+}
+
+#[test]
+fn test_compile_syntax_error() {
+    let res = ContextBuilder::default()
+        .with_rule_string(Some("rule r { condition: }"))
+        .build();
+
+    assert!(matches!(
+        res,
+        Err(YariError::CompileError(diagnostics)) if diagnostics.iter().all(Diagnostic::is_error)
+    ));
+}
+
+#[test]
+fn test_compile_warnings() {
+    let context = ContextBuilder::default()
+        .with_rule_string(Some(
+            "rule r {
+    strings:
+        $s00 = { 00 }
+    condition:
+        $s00
+}",
+        ))
+        .build()
+        .unwrap();
+
+    let diagnostics = context.diagnostics();
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.severity == Severity::Warning && d.rule.as_deref() == Some("r")));
+}
+
+#[test]
+fn test_compile_without_warnings() {
+    let context = common::context_with_pe_sample_and_rule();
+    assert!(context.diagnostics().is_empty());
+}
+
+#[test]
+fn test_compile_rules_with_nul() {
+    let res = ContextBuilder::default()
+        .with_rule_string(Some(
+            "rule r { condition: true }\0rule s { condition: true }",
+        ))
+        .build();
+    assert_eq!(
+        res.err(),
+        Some(YariError::ContextBuilderError(
+            "rules of the namespace \"default\" contain NUL".to_string()
+        ))
+    );
+
+    let res = ContextBuilder::default()
+        .with_rule_string_ns("a\0b", "rule r { condition: true }")
+        .build();
+    assert!(matches!(res, Err(YariError::ContextBuilderError(_))));
+}
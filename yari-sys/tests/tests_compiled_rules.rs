@@ -0,0 +1,64 @@
+extern crate yari_sys;
+
+mod common;
+
+use yari_sys::ContextBuilder;
+use yari_sys::YariError;
+use yari_sys::YrValue;
+
+#[test]
+fn test_compiled_rules_strings() {
+    let path = common::compiled_rules("strings");
+    let mut context = common::context_with_compiled_rules(&path);
+
+    assert_eq!(context.eval("r|$s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|#s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|@s00[1]"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|$s01"), Ok(YrValue::Integer(0)));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_compiled_rules_rule_status() {
+    let path = common::compiled_rules("status");
+    let mut context = common::context_with_compiled_rules(&path);
+
+    assert_eq!(context.eval("r"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("not_matching"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("default:r"), Ok(YrValue::Integer(1)));
+
+    context.set_sample(b"".to_vec()).unwrap();
+    assert_eq!(context.eval("r"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("not_matching"), Ok(YrValue::Integer(1)));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_compiled_rules_missing_source() {
+    let path = common::compiled_rules("source");
+    let mut context = common::context_with_compiled_rules(&path);
+
+    assert_eq!(
+        context.eval("r|for any i in (1..#s00): (@s00[i] == 0)"),
+        Err(YariError::RuleSourceMissingError)
+    );
+    assert_eq!(context.explain("r"), Err(YariError::RuleSourceMissingError));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_compiled_rules_invalid() {
+    let res = ContextBuilder::default()
+        .with_compiled_rules("/dev/null/does_not_exist.yarc")
+        .build();
+    assert!(matches!(res, Err(YariError::RulesLoadError(_))));
+
+    let res = ContextBuilder::default()
+        .with_rule_string(Some("rule r { condition: true }"))
+        .with_compiled_rules("/dev/null/does_not_exist.yarc")
+        .build();
+    assert!(matches!(res, Err(YariError::ContextBuilderError(_))));
+}
@@ -0,0 +1,82 @@
+extern crate yari_sys;
+
+mod common;
+
+use yari_sys::disasm::Operand;
+use yari_sys::YariError;
+
+#[test]
+fn test_disassemble_rule_boundaries() {
+    let context = common::context_with_disassembled_rules();
+    let instructions = context.disassemble("r").unwrap();
+
+    let first = instructions.first().unwrap();
+    assert_eq!(first.mnemonic, "init_rule");
+    assert_eq!(first.operands[0], Operand::Rule("default:r".to_string()));
+
+    let last = instructions.last().unwrap();
+    assert_eq!(last.mnemonic, "match_rule");
+    assert_eq!(last.operands[0], Operand::Rule("default:r".to_string()));
+
+    // Offsets increase through the rule
+    assert!(instructions.windows(2).all(|w| w[0].offset < w[1].offset));
+}
+
+#[test]
+fn test_disassemble_strings_and_jumps() {
+    let context = common::context_with_disassembled_rules();
+    let instructions = context.disassemble("r").unwrap();
+
+    assert!(instructions
+        .iter()
+        .any(|i| i.operands.contains(&Operand::String("$s00".to_string()))));
+    assert!(instructions.iter().any(|i| i.mnemonic == "found"));
+    assert!(instructions.iter().any(|i| i.mnemonic == "filesize"));
+
+    // Jumps of the rule are labeled relative to the rule start
+    let jumps: Vec<_> = instructions
+        .iter()
+        .flat_map(|i| i.operands.iter())
+        .filter_map(|o| match o {
+            Operand::Jump { label, .. } => Some(label),
+            _ => None,
+        })
+        .collect();
+    assert!(!jumps.is_empty());
+    assert!(jumps.iter().all(|label| label.starts_with("default:r+")));
+}
+
+#[test]
+fn test_disassemble_objects() {
+    let context = common::context_with_disassembled_rules();
+    let instructions = context.disassemble("sections").unwrap();
+
+    assert!(instructions.iter().any(
+        |i| i.mnemonic == "obj_load" && i.operands[0] == Operand::Identifier("pe".to_string())
+    ));
+    assert!(instructions.iter().any(|i| i.mnemonic == "obj_field"
+        && i.operands[0] == Operand::Identifier("number_of_sections".to_string())));
+    assert!(instructions
+        .iter()
+        .any(|i| i.operands.contains(&Operand::Literal(".text".to_string()))));
+}
+
+#[test]
+fn test_disassemble_namespace() {
+    let context = common::context_with_disassembled_rules();
+    let instructions = context.disassemble("other:r").unwrap();
+    assert_eq!(
+        instructions[0].operands[0],
+        Operand::Rule("other:r".to_string())
+    );
+    assert!(instructions.iter().all(|i| i.mnemonic != "filesize"));
+}
+
+#[test]
+fn test_disassemble_missing_rule() {
+    let context = common::context_with_disassembled_rules();
+    assert_eq!(
+        context.disassemble("missing"),
+        Err(YariError::RuleMissingError)
+    );
+}
@@ -1,4 +1,8 @@
 use log::debug;
+use yari_sys::dump::ObjectKind;
+use yari_sys::DumpFormat;
+use yari_sys::Module;
+use yari_sys::YrValue;
 use yari_sys::MODULES;
 
 mod common;
@@ -42,3 +46,74 @@ fn test_function_dump_elf() {
         context.dump_module(*module).unwrap();
     }
 }
+
+#[test]
+fn test_dump_module_tree_pe() {
+    let mut context = common::context_with_pe_sample_and_rule();
+
+    let tree = context.dump_module(Module::Pe).unwrap();
+    assert_eq!(tree.name, "pe");
+    assert_eq!(tree.kind, ObjectKind::Structure);
+
+    let sections = tree
+        .children
+        .iter()
+        .find(|c| c.name == "number_of_sections")
+        .unwrap();
+    assert_eq!(sections.kind, ObjectKind::Integer);
+    assert_eq!(sections.value, Some(YrValue::Integer(4)));
+
+    let exports = tree.children.iter().find(|c| c.name == "exports").unwrap();
+    assert_eq!(exports.kind, ObjectKind::Function);
+    assert!(exports.prototypes.contains(&"s".to_string()));
+    assert_eq!(exports.return_kind, Some(ObjectKind::Integer));
+}
+
+#[test]
+fn test_dump_module_formats() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let tree = context.dump_module(Module::Pe).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&tree.format(DumpFormat::Json)).unwrap();
+    assert_eq!(json["name"], "pe");
+    assert_eq!(json["kind"], "structure");
+    let sections = json["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "number_of_sections")
+        .unwrap();
+    assert_eq!(sections["kind"], "integer");
+    assert_eq!(sections["value"], 4);
+
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&tree.format(DumpFormat::Yaml)).unwrap();
+    assert_eq!(yaml["name"], "pe");
+    let exports = yaml["children"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "exports")
+        .unwrap();
+    assert_eq!(exports["kind"], "function");
+    assert_eq!(exports["return_type"], "integer");
+
+    let text = tree.format(DumpFormat::Text);
+    assert!(text.contains("\t\"number_of_sections\" = 0x4\n"));
+}
+
+#[test]
+fn test_dump_module_undefined_values() {
+    let mut context = common::context();
+    let tree = context.dump_module(Module::Pe).unwrap();
+
+    let sections = tree
+        .children
+        .iter()
+        .find(|c| c.name == "number_of_sections")
+        .unwrap();
+    assert_eq!(sections.value, None);
+
+    let json = serde_json::to_value(sections).unwrap();
+    assert_eq!(json["kind"], "integer");
+    assert!(json["value"].is_null());
+}
@@ -2,8 +2,11 @@ extern crate yari_sys;
 
 mod common;
 
+use indexmap::IndexMap;
+use std::path::Path;
 use yari_sys::error::YariError;
 use yari_sys::ContextBuilder;
+use yari_sys::ExternalVariable;
 use yari_sys::YrValue;
 
 #[test]
@@ -37,6 +40,61 @@ fn test_math_mean_empty_string() {
     assert!(std::matches!(res, Ok(YrValue::Float(val)) if val.is_nan()));
 }
 
+#[test]
+fn test_math_in_range() {
+    let mut context = common::context();
+
+    assert_eq!(
+        context.eval("math.in_range(7.5, 7.0, 8.0)"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("math.in_range(1.0, 63.9, 64.1)"),
+        Ok(YrValue::Integer(0))
+    );
+}
+
+#[test]
+fn test_math_deviation_string() {
+    let mut context = common::context();
+
+    let Ok(YrValue::Float(res)) = context.eval("math.deviation(\"data\", 64.1)") else {
+        panic!("expected float");
+    };
+    assert!((res - 38.4).abs() < 1e-9);
+}
+
+#[test]
+fn test_math_deviation_range() {
+    let mut context = ContextBuilder::default()
+        .with_sample_bytes(b"data".to_vec())
+        .build()
+        .unwrap();
+
+    let Ok(YrValue::Float(res)) = context.eval("math.deviation(0, 4, 64.1)") else {
+        panic!("expected float");
+    };
+    assert!((res - 38.4).abs() < 1e-9);
+
+    let mut context = common::context_with_pe_signed_sample();
+    let res = context.eval("math.deviation(0, 100, 89.5)");
+    assert!(matches!(res, Ok(YrValue::Float(f)) if f > 0.0));
+}
+
+#[test]
+fn test_math_float_arguments_mismatch() {
+    let mut context = common::context();
+
+    assert_eq!(
+        context.eval("math.max(1.0, 2.0)"),
+        Err(YariError::SymbolNotFound("math.max".to_string()))
+    );
+    assert_eq!(
+        context.eval("math.in_range(7, 7, 8)"),
+        Err(YariError::SymbolNotFound("math.in_range".to_string()))
+    );
+}
+
 #[test]
 fn test_cuckoo_dot_star_match() {
     let mut context = common::context_with_cuckoo();
@@ -105,10 +163,10 @@ fn test_pe_imphash_without_sample() {
 }
 
 #[test]
-fn test_yara_ascii_escaped_string() {
+fn test_yara_binary_string() {
     let mut context = common::context_with_pe_signed_sample();
     let res = context.eval("pe.rich_signature.clear_data");
-    assert_eq!(res, Ok(YrValue::String(Some("DanS\\x00\\x00\\x00\\x00\\x00\\x00\\x00\\x00\\x00\\x00\\x00\\x00V\\x1f\\x13\\x00%\\x00\\x00\\x00\\x00\\x00\\x00\\x00/\\x00\\x00\\x00b\\x1f\\x13\\x00\\x12\\x00\\x00\\x00\\x83\\x1c\\x0e\\x00\\x1d\\x00\\x00\\x006&\\n\\x00\\x8c\\x00\\x00\\x00\\x00\\x00\\x01\\x00\\x02\\x02\\x00\\x00\\xff \\x04\\x00\\t\\x00\\x00\\x006&\\x0b\\x00y\\x00\\x00\\x00\\xc7\\x06\\x06\\x00\\x01\\x00\\x00\\x00".to_string()))));
+    assert_eq!(res, Ok(YrValue::Bytes(b"DanS\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00V\x1f\x13\x00%\x00\x00\x00\x00\x00\x00\x00/\x00\x00\x00b\x1f\x13\x00\x12\x00\x00\x00\x83\x1c\x0e\x00\x1d\x00\x00\x006&\n\x00\x8c\x00\x00\x00\x00\x00\x01\x00\x02\x02\x00\x00\xff \x04\x00\t\x00\x00\x006&\x0b\x00y\x00\x00\x00\xc7\x06\x06\x00\x01\x00\x00\x00".to_vec())));
 }
 
 #[test]
@@ -461,3 +519,585 @@ fn test_eval_integer_postfix() {
         YrValue::Integer(1)
     );
 }
+
+#[test]
+fn test_native_arithmetic() {
+    let mut context = common::context();
+
+    assert_eq!(context.eval("1 + 2 * 3"), Ok(YrValue::Integer(7)));
+    assert_eq!(context.eval("(1 + 2) * 3"), Ok(YrValue::Integer(9)));
+    assert_eq!(context.eval("7 \\ 2"), Ok(YrValue::Integer(3)));
+    assert_eq!(context.eval("7 % 4"), Ok(YrValue::Integer(3)));
+    assert_eq!(context.eval("1 << 4 | 0x1"), Ok(YrValue::Integer(17)));
+    assert_eq!(context.eval("~0 & 0xff ^ 0xf"), Ok(YrValue::Integer(0xf0)));
+    assert_eq!(context.eval("-5 + 1.5"), Ok(YrValue::Float(-3.5)));
+    assert_eq!(context.eval("2KB"), Ok(YrValue::Integer(2048)));
+    assert!(context.eval("1 \\ 0").unwrap().is_undefined());
+}
+
+#[test]
+fn test_native_boolean_without_rule() {
+    let mut context = common::context_with_elf_sample();
+
+    assert_eq!(
+        context.eval("elf.number_of_sections == 30 and elf.number_of_segments > 3"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("pe.number_of_sections > 3 and elf.number_of_sections == 30"),
+        Ok(YrValue::Integer(0))
+    );
+    assert_eq!(
+        context.eval("pe.number_of_sections > 3 or elf.entry_point == entrypoint"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("not elf.number_of_sections < 4"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(context.eval("entrypoint"), Ok(YrValue::Integer(4160)));
+}
+
+#[test]
+fn test_native_undefined() {
+    let mut context = common::context_with_elf_sample();
+
+    assert!(context
+        .eval("pe.number_of_sections + 1")
+        .unwrap()
+        .is_undefined());
+    assert!(context
+        .eval("not pe.number_of_sections == 4")
+        .unwrap()
+        .is_undefined());
+    assert_eq!(
+        context.eval("defined pe.number_of_sections"),
+        Ok(YrValue::Integer(0))
+    );
+    assert_eq!(
+        context.eval("defined elf.number_of_sections"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_native_string_operators() {
+    let mut context = common::context_with_pe_signed_sample();
+
+    assert_eq!(
+        context.eval("pe.version_info[\"OriginalFilename\"] == \"Demo.EXE\""),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("pe.version_info[\"OriginalFilename\"] iequals \"demo.exe\""),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("pe.version_info[\"FileDescription\"] contains \"MFC\""),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("pe.version_info[\"FileDescription\"] endswith \"application\""),
+        Ok(YrValue::Integer(0))
+    );
+    assert_eq!(
+        context.eval("pe.version_info[\"FileDescription\"] iendswith \"application\""),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_native_type_error() {
+    let mut context = common::context();
+
+    assert_eq!(context.eval("\"abc\" + 1"), Err(YariError::EvalError));
+    assert_eq!(context.eval("1.5 % 2"), Err(YariError::EvalError));
+}
+
+#[test]
+fn test_native_with_rule_context() {
+    let mut context = common::context_with_pe_sample_and_rule();
+
+    assert_eq!(
+        context.eval("r|$s00 and pe.number_of_sections == 4"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(context.eval("r|#s00 + 1 == 2"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|$s00 and $s01"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("filesize == 8704"), Ok(YrValue::Integer(1)));
+}
+
+#[test]
+fn test_native_with_rule_names() {
+    let mut context = common::context_with_pe_sample_and_rule();
+
+    assert_eq!(context.eval("PRIVATE and not r"), Ok(YrValue::Integer(1)));
+}
+
+#[test]
+fn test_externals() {
+    let mut context = common::context_with_externals_and_rule();
+
+    assert_eq!(context.eval("int_var"), Ok(YrValue::Integer(10)));
+    assert_eq!(context.eval("float_var"), Ok(YrValue::Float(1.5)));
+    assert_eq!(context.eval("bool_var"), Ok(YrValue::Integer(1)));
+    assert_eq!(
+        context.eval("str_var"),
+        Ok(YrValue::String(Some("test".to_string())))
+    );
+    assert_eq!(
+        context.eval("int_var * 2 + float_var"),
+        Ok(YrValue::Float(21.5))
+    );
+}
+
+#[test]
+fn test_externals_in_rule() {
+    let mut context = common::context_with_externals_and_rule();
+
+    assert_eq!(context.eval("r"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|int_var"), Ok(YrValue::Integer(10)));
+    assert_eq!(
+        context.eval("r|str_var matches /te.t/"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_externals_invalid_name() {
+    let res = ContextBuilder::default()
+        .with_external_int("invalid-name", 1)
+        .build();
+    assert!(matches!(res, Err(YariError::ContextBuilderError(_))));
+}
+
+#[test]
+fn test_externals_string_with_nul() {
+    let res = ContextBuilder::default()
+        .with_external_string("str_var", "a\0b")
+        .build();
+    assert!(matches!(res, Err(YariError::ContextBuilderError(_))));
+}
+
+#[test]
+fn test_parse_external_variable_str() {
+    assert_eq!(
+        ContextBuilder::parse_external_variable_str("var=10"),
+        Some(("var".to_string(), ExternalVariable::Integer(10)))
+    );
+    assert_eq!(
+        ContextBuilder::parse_external_variable_str("var=a=b"),
+        Some((
+            "var".to_string(),
+            ExternalVariable::String("a=b".to_string())
+        ))
+    );
+    assert_eq!(ContextBuilder::parse_external_variable_str("var"), None);
+    assert_eq!(ContextBuilder::parse_external_variable_str("=10"), None);
+}
+
+#[test]
+fn test_sample_bytes() {
+    let mut context = common::context_with_pe_sample_bytes_and_rule();
+
+    assert_eq!(
+        context.eval("pe.number_of_sections"),
+        Ok(YrValue::Integer(4))
+    );
+    assert_eq!(context.eval("filesize"), Ok(YrValue::Integer(8704)));
+    assert_eq!(context.eval("entrypoint"), Ok(YrValue::Integer(0x876)));
+    assert_eq!(context.eval("r|@s00[1]"), Ok(YrValue::Integer(1212)));
+    assert_eq!(context.eval("r"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|#s00 == 1"), Ok(YrValue::Integer(1)));
+}
+
+#[test]
+fn test_sample_reader() {
+    let mut context = ContextBuilder::default()
+        .with_sample_reader(&b"some test data"[..])
+        .unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(context.eval("filesize"), Ok(YrValue::Integer(14)));
+    assert_eq!(
+        context.eval("hash.md5(0, 4) == hash.md5(\"some\")"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_sample_empty_bytes() {
+    let mut context = ContextBuilder::default()
+        .with_sample_bytes(Vec::new())
+        .build()
+        .unwrap();
+
+    assert_eq!(context.eval("filesize"), Ok(YrValue::Integer(0)));
+}
+
+#[test]
+fn test_function_expression_arguments() {
+    let mut context = ContextBuilder::default()
+        .with_sample_bytes(b"some test data".to_vec())
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        context.eval("hash.md5(0, filesize) == hash.md5(\"some test data\")"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("hash.md5(filesize - 4, 2 * 2) == hash.md5(\"data\")"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("hash.md5(\"\\x41\") == hash.md5(\"A\")"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_function_value_arguments() {
+    let mut context = common::context_with_pe_signed_sample();
+
+    let res =
+        context.eval("math.entropy(pe.sections[0].raw_data_offset, pe.sections[0].raw_data_size)");
+    assert!(matches!(res, Ok(YrValue::Float(f)) if f > 0.0));
+
+    assert_eq!(
+        context.eval("pe.imports(pe.import_details[0].library_name) > 0"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_function_nested_call() {
+    let mut context = common::context_with_pe_signed_sample();
+
+    let res = context.eval("math.in_range(math.entropy(0, filesize), 0.0, 8.0)");
+    assert_eq!(res, Ok(YrValue::Integer(1)));
+}
+
+#[test]
+fn test_function_undefined_argument() {
+    let mut context = common::context_with_elf_sample();
+
+    let res = context.eval("hash.md5(0, pe.number_of_sections)").unwrap();
+    assert!(res.is_undefined());
+}
+
+#[test]
+fn test_set_sample() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    assert_eq!(
+        context.eval("pe.number_of_sections"),
+        Ok(YrValue::Integer(4))
+    );
+    assert_eq!(context.eval("r|$s00"), Ok(YrValue::Integer(1)));
+
+    context.set_sample(b"Hello world".to_vec()).unwrap();
+    assert_eq!(context.eval("filesize"), Ok(YrValue::Integer(11)));
+    assert!(context
+        .eval("pe.number_of_sections")
+        .unwrap()
+        .is_undefined());
+    assert_eq!(context.eval("r|@s00[1]"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|#s00"), Ok(YrValue::Integer(1)));
+
+    context.set_sample(Vec::new()).unwrap();
+    assert_eq!(context.eval("r|$s00"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|#s00"), Ok(YrValue::Integer(0)));
+}
+
+#[test]
+fn test_set_sample_file() {
+    let test_root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut context = common::context_with_elf_sample();
+    assert_eq!(
+        context.eval("elf.number_of_sections"),
+        Ok(YrValue::Integer(30))
+    );
+
+    context
+        .set_sample(test_root.join("tests/assets/pe_hello_world"))
+        .unwrap();
+    assert_eq!(
+        context.eval("pe.number_of_sections"),
+        Ok(YrValue::Integer(4))
+    );
+    assert!(context
+        .eval("elf.number_of_sections")
+        .unwrap()
+        .is_undefined());
+    assert_eq!(context.eval("entrypoint"), Ok(YrValue::Integer(0x876)));
+}
+
+#[test]
+fn test_set_sample_keeps_module_data() {
+    let mut context = common::context_with_cuckoo();
+    assert_eq!(
+        context.eval("cuckoo.filesystem.file_access(/.*/)"),
+        Ok(YrValue::Integer(1))
+    );
+
+    context.set_sample(b"data".to_vec()).unwrap();
+    assert_eq!(
+        context.eval("cuckoo.filesystem.file_access(/.*/)"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_set_sample_missing_file() {
+    let mut context = common::context();
+    assert!(matches!(
+        context.set_sample("/this/file/does/not/exist"),
+        Err(YariError::SampleError(_))
+    ));
+}
+
+#[test]
+fn test_set_sample_directory_keeps_current_sample() {
+    let test_root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut context = common::context_with_pe_sample_and_rule();
+    let filesize = context.eval("filesize").unwrap();
+
+    assert!(matches!(
+        context.set_sample(test_root.join("tests/assets")),
+        Err(YariError::SampleError(_))
+    ));
+    assert_eq!(context.eval("filesize"), Ok(filesize));
+    assert_eq!(
+        context.eval("pe.number_of_sections"),
+        Ok(YrValue::Integer(4))
+    );
+}
+
+fn context_with_string_sets() -> yari_sys::Context {
+    ContextBuilder::default()
+        .with_sample_bytes(b"Hello world, Hello again".to_vec())
+        .with_rule_string(Some(
+            "rule r {
+    strings:
+        $s00 = \"Hello\"
+        $s01 = \"world\"
+        $s02 = \"not found\"
+        $ = \"again\"
+        $ = \"o\"
+        $a = \"l\"
+    condition:
+        any of them
+}",
+        ))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_string_wildcards() {
+    let mut context = context_with_string_sets();
+
+    assert_eq!(context.eval("r|#s*"), Ok(YrValue::Integer(3)));
+    assert_eq!(context.eval("r|#*"), Ok(YrValue::Integer(12)));
+    assert_eq!(
+        context.eval("r|$s*"),
+        Ok(YrValue::Dictionary(IndexMap::from([
+            ("$s00".to_string(), YrValue::Integer(1)),
+            ("$s01".to_string(), YrValue::Integer(1)),
+            ("$s02".to_string(), YrValue::Integer(0)),
+        ])))
+    );
+    assert_eq!(context.eval("r|#x*"), Err(YariError::UndeclaredStringError));
+    assert_eq!(context.eval("r|@s*[1]"), Err(YariError::EvalError));
+    assert_eq!(context.eval("r|!*[1]"), Err(YariError::EvalError));
+}
+
+#[test]
+fn test_anonymous_strings() {
+    let mut context = context_with_string_sets();
+
+    assert_eq!(context.eval("r|$[1]"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|#[2]"), Ok(YrValue::Integer(3)));
+    assert_eq!(context.eval("r|@[1][1]"), Ok(YrValue::Integer(19)));
+    assert_eq!(context.eval("r|![2][1]"), Ok(YrValue::Integer(1)));
+    assert_eq!(
+        context.eval("r|$[3]"),
+        Err(YariError::UndeclaredStringError)
+    );
+    assert_eq!(
+        context.eval("r|$*"),
+        Ok(YrValue::Dictionary(IndexMap::from([
+            ("$s00".to_string(), YrValue::Integer(1)),
+            ("$s01".to_string(), YrValue::Integer(1)),
+            ("$s02".to_string(), YrValue::Integer(0)),
+            ("$[1]".to_string(), YrValue::Integer(1)),
+            ("$[2]".to_string(), YrValue::Integer(1)),
+            ("$a".to_string(), YrValue::Integer(1)),
+        ])))
+    );
+}
+
+#[test]
+fn test_of_expressions() {
+    let mut context = context_with_string_sets();
+
+    assert_eq!(context.eval("r|any of ($s*)"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|all of ($s*)"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|none of ($s02)"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|2 of them"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|6 of them"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|5 of them"), Ok(YrValue::Integer(1)));
+    assert_eq!(
+        context.eval("r|all of ($s00, $s0*)"),
+        Ok(YrValue::Integer(0))
+    );
+    assert_eq!(
+        context.eval("r|all of ($s00, $[1])"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(context.eval("r|80% of them"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|90% of them"), Ok(YrValue::Integer(0)));
+    assert_eq!(
+        context.eval("r|(#s00) of ($s*) and not all of them"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("any of them"),
+        Err(YariError::RuleMissingError)
+    );
+}
+
+#[test]
+fn test_string_at_and_in() {
+    let mut context = context_with_string_sets();
+
+    assert_eq!(context.eval("r|$s00 at 0"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|$s00 at 13"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|$s00 at 1"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|$s01 at 2 * 3"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|$s00 in (1..13)"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("r|$s00 in (1..12)"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|#a in (0..10)"), Ok(YrValue::Integer(3)));
+    assert_eq!(
+        context.eval("r|#a in (0..filesize)"),
+        Ok(YrValue::Integer(5))
+    );
+    assert_eq!(
+        context.eval("r|#a in (0..100) == #a"),
+        Ok(YrValue::Integer(1))
+    );
+
+    // Entrypoint of the sample which is not an executable is undefined
+    assert_eq!(
+        context.eval("r|$s00 at entrypoint"),
+        Ok(YrValue::Integer(0))
+    );
+    assert!(context
+        .eval("r|#s00 in (entrypoint..100)")
+        .unwrap()
+        .is_undefined());
+}
+
+#[test]
+fn test_string_at_entrypoint() {
+    let test_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut context = ContextBuilder::default()
+        .with_sample(Some(test_root.join("tests/assets/pe_hello_world")))
+        .with_rule_string(Some(
+            "rule r {
+    strings:
+        $ep = { e8 03 06 00 00 e9 }
+    condition:
+        $ep at entrypoint
+}",
+        ))
+        .build()
+        .unwrap();
+
+    assert_eq!(context.eval("r|$ep at entrypoint"), Ok(YrValue::Integer(1)));
+    assert_eq!(
+        context.eval("r|$ep at entrypoint + 1"),
+        Ok(YrValue::Integer(0))
+    );
+    assert_eq!(
+        context.eval("r|#ep in (entrypoint..entrypoint + 5)"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_binary_string_operations() {
+    let mut context = common::context_with_pe_signed_sample();
+    assert_eq!(
+        context.eval("pe.rich_signature.clear_data startswith \"DanS\\x00\""),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("pe.rich_signature.clear_data contains \"\\xff \\x04\""),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval(
+            "hash.md5(pe.rich_signature.raw_data) == \
+             hash.md5(pe.rich_signature.offset, pe.rich_signature.length)"
+        ),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_string_literal_is_not_escaped() {
+    let mut context = common::context();
+    assert_eq!(
+        context.eval("\"a\\\\b\\n\""),
+        Ok(YrValue::String(Some("a\\b\n".to_string())))
+    );
+    assert_eq!(context.eval("\"\\xff\""), Ok(YrValue::Bytes(vec![0xff])));
+}
+
+#[test]
+fn test_structure_keeps_declaration_order() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let res = context.eval("pe.sections[0]").unwrap();
+
+    let YrValue::Structure(Some(members)) = res else {
+        panic!("Expected `Structure(Some())`")
+    };
+    let keys: Vec<_> = members.keys().take(5).map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        [
+            "name",
+            "full_name",
+            "characteristics",
+            "virtual_address",
+            "virtual_size"
+        ]
+    );
+}
+
+#[test]
+fn test_dictionary_keeps_insertion_order() {
+    let mut context = common::context_with_pe_signed_sample();
+
+    let YrValue::Dictionary(dictionary) = context.eval("pe.version_info").unwrap() else {
+        panic!("Expected `Dictionary` from eval")
+    };
+    let YrValue::Array(list) = context.eval("pe.version_info_list").unwrap() else {
+        panic!("Expected `Array` from eval")
+    };
+
+    let list_keys: Vec<_> = list
+        .iter()
+        .map(|item| match item {
+            YrValue::Structure(Some(s)) => s["key"].clone(),
+            _ => panic!("Expected `Structure(Some())`"),
+        })
+        .collect();
+    let dictionary_keys: Vec<_> = dictionary
+        .keys()
+        .map(|k| YrValue::String(Some(k.clone())))
+        .collect();
+    assert_eq!(dictionary_keys, list_keys);
+}
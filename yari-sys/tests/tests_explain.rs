@@ -0,0 +1,75 @@
+extern crate yari_sys;
+
+mod common;
+
+use yari_sys::explain::Outcome;
+use yari_sys::{YariError, YrValue};
+
+#[test]
+fn test_explain_short_circuit_and() {
+    let mut context = common::context_with_explained_rules();
+    let explanation = context.explain("r").unwrap();
+
+    assert_eq!(explanation.expression, "$s01 and filesize > 5");
+    assert_eq!(explanation.value(), Some(&YrValue::Integer(0)));
+    assert_eq!(explanation.children.len(), 2);
+    assert_eq!(explanation.children[0].expression, "$s01");
+    assert_eq!(
+        explanation.children[0].outcome,
+        Outcome::Value(YrValue::Integer(0))
+    );
+    assert_eq!(explanation.children[1].expression, "filesize > 5");
+    assert_eq!(explanation.children[1].outcome, Outcome::ShortCircuited);
+    assert!(explanation.children[1].children.is_empty());
+}
+
+#[test]
+fn test_explain_short_circuit_or() {
+    let mut context = common::context_with_explained_rules();
+    let explanation = context.explain("matching").unwrap();
+
+    assert_eq!(explanation.value(), Some(&YrValue::Integer(1)));
+    assert_eq!(explanation.children[0].children.len(), 2);
+    assert_eq!(
+        explanation.children[0].children[0].outcome,
+        Outcome::Value(YrValue::Integer(11))
+    );
+    assert_eq!(explanation.children[1].outcome, Outcome::ShortCircuited);
+}
+
+#[test]
+fn test_explain_undefined() {
+    let mut context = common::context_with_explained_rules();
+    let explanation = context.explain("not_pe").unwrap();
+
+    assert_eq!(explanation.value(), Some(&YrValue::Integer(1)));
+
+    let comparison = &explanation.children[0];
+    assert!(comparison.value().unwrap().is_undefined());
+    assert!(comparison.children[0].value().unwrap().is_undefined());
+    assert!(comparison.to_string().contains("undefined (!)"));
+    assert_eq!(
+        explanation.children[1].outcome,
+        Outcome::Value(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_explain_display() {
+    let mut context = common::context_with_explained_rules();
+    let explanation = context.explain("r").unwrap();
+
+    assert_eq!(
+        explanation.to_string(),
+        "$s01 and filesize > 5 => 0
+├── $s01 => 0
+└── filesize > 5 => short-circuited
+"
+    );
+}
+
+#[test]
+fn test_explain_missing_rule() {
+    let mut context = common::context_with_explained_rules();
+    assert_eq!(context.explain("missing"), Err(YariError::RuleMissingError));
+}
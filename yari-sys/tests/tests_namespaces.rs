@@ -0,0 +1,125 @@
+extern crate yari_sys;
+
+use std::path::Path;
+use yari_sys::ContextBuilder;
+use yari_sys::YariError;
+use yari_sys::YrValue;
+
+fn context_with_namespaces() -> yari_sys::Context {
+    ContextBuilder::default()
+        .with_sample_bytes(b"Hello world".to_vec())
+        .with_rule_string_ns(
+            "a",
+            "rule r {
+    strings:
+        $s00 = \"Hello\"
+    condition:
+        $s00
+}",
+        )
+        .with_rule_string_ns(
+            "b",
+            "rule r {
+    strings:
+        $s00 = \"not found\"
+    condition:
+        $s00
+}",
+        )
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_namespace_rule_context() {
+    let mut context = context_with_namespaces();
+
+    assert_eq!(context.eval("a:r|$s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("b:r|$s00"), Ok(YrValue::Integer(0)));
+    assert_eq!(context.eval("r|$s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("c:r|$s00"), Err(YariError::RuleMissingError));
+}
+
+#[test]
+fn test_namespace_fallback() {
+    let mut context = context_with_namespaces();
+
+    assert_eq!(
+        context.eval("a:r|for any i in (1..#s00): (@s00[i] == 0)"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("b:r|for any i in (1..#s00): (@s00[i] == 0)"),
+        Ok(YrValue::Integer(0))
+    );
+}
+
+#[test]
+fn test_include_relative_to_file() {
+    let test_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut context = ContextBuilder::default()
+        .with_sample_bytes(b"Hello world".to_vec())
+        .with_rule_file_ns("main", test_root.join("tests/assets/rules/main.yar"))
+        .build()
+        .unwrap();
+
+    assert_eq!(context.eval("main:common|$s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("main:strings|$s00"), Ok(YrValue::Integer(1)));
+    assert_eq!(context.eval("main:main|common"), Ok(YrValue::Integer(1)));
+    assert_eq!(
+        context.eval("main:main|filesize == 11 and common"),
+        Ok(YrValue::Integer(1))
+    );
+}
+
+#[test]
+fn test_include_rule_from_included_file() {
+    let test_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut context = ContextBuilder::default()
+        .with_sample_bytes(b"Hello world".to_vec())
+        .with_rule_file_ns("main", test_root.join("tests/assets/rules/main.yar"))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        context.eval("main:strings|for any i in (1..#s00): (@s00[i] == 6)"),
+        Ok(YrValue::Integer(1))
+    );
+    assert_eq!(
+        context.eval("main:common|for any i in (1..#s00): (@s00[i] == 6)"),
+        Ok(YrValue::Integer(0))
+    );
+
+    let explanation = context.explain("main:common").unwrap();
+    assert_eq!(explanation.expression, "$s00 and strings");
+    assert_eq!(explanation.value(), Some(&YrValue::Integer(1)));
+}
+
+#[test]
+fn test_include_missing_file() {
+    let res = ContextBuilder::default()
+        .with_rule_string(Some("include \"does_not_exist.yar\""))
+        .build();
+
+    assert!(matches!(res, Err(YariError::CompileError(_))));
+}
+
+#[test]
+fn test_rule_string_replaces_default_rules() {
+    let context = ContextBuilder::default()
+        .with_rule_string(Some("rule first { condition: true }"))
+        .with_rule_string_ns("other", "rule r { condition: true }")
+        .with_rule_string(Some("rule second { condition: true }"))
+        .build()
+        .unwrap();
+
+    let names: Vec<String> = context.rules().iter().map(|r| r.qualified_name()).collect();
+    assert_eq!(names, vec!["other:r", "default:second"]);
+
+    let context = ContextBuilder::default()
+        .with_rule_string(Some("rule first { condition: true }"))
+        .with_rule_string(None::<String>)
+        .build()
+        .unwrap();
+    assert!(context.rules().is_empty());
+}
@@ -0,0 +1,79 @@
+extern crate yari_sys;
+
+mod common;
+
+#[test]
+fn test_profile_disabled() {
+    let context = common::context_with_profiled_rules(false);
+    assert!(context.profile().is_none());
+}
+
+#[test]
+fn test_profile_rules_and_strings() {
+    let context = common::context_with_profiled_rules(true);
+    let profile = context.profile().unwrap();
+
+    let mut rules: Vec<_> = profile.rules.iter().map(|r| r.rule.as_str()).collect();
+    rules.sort();
+    assert_eq!(rules, vec!["default:no_strings", "default:r"]);
+
+    let rule = profile
+        .rules
+        .iter()
+        .find(|r| r.rule == "default:r")
+        .unwrap();
+    let strings: Vec<_> = profile
+        .strings
+        .iter()
+        .filter(|s| s.rule == "default:r")
+        .collect();
+    assert_eq!(strings.len(), 2);
+    assert_eq!(
+        rule.atom_matches,
+        strings.iter().map(|s| s.atom_matches).sum::<u64>()
+    );
+
+    let s00 = strings.iter().find(|s| s.identifier == "$s00").unwrap();
+    assert_eq!(s00.atom_matches, 2);
+    assert!(strings.iter().any(|s| s.identifier == "$[1]"));
+
+    // Strings are sorted by the verification time
+    assert!(profile
+        .strings
+        .windows(2)
+        .all(|w| w[0].match_time >= w[1].match_time));
+}
+
+#[test]
+fn test_profile_does_not_change_matches() {
+    let mut context = common::context_with_profiled_rules(true);
+    assert_eq!(context.eval("r|#s00"), Ok(yari_sys::YrValue::Integer(2)));
+    assert_eq!(context.eval("r"), Ok(yari_sys::YrValue::Integer(1)));
+}
+
+#[test]
+fn test_profile_set_sample() {
+    let mut context = common::context_with_profiled_rules(true);
+    context.set_sample(b"ooo".to_vec()).unwrap();
+
+    let profile = context.profile().unwrap();
+    let s00 = profile
+        .strings
+        .iter()
+        .find(|s| s.identifier == "$s00")
+        .unwrap();
+    assert_eq!(s00.atom_matches, 3);
+}
+
+#[test]
+fn test_profile_rule_cost() {
+    let context = common::context_with_profiled_rules(true);
+    let profile = context.profile().unwrap();
+
+    // libyara reports the rule cost only with the `profiling` feature
+    assert_eq!(profile.rules.len(), 2);
+    assert!(profile
+        .rules
+        .iter()
+        .all(|r| r.cost.is_some() == cfg!(feature = "profiling")));
+}
@@ -0,0 +1,73 @@
+use yari_sys::render::Renderer;
+use yari_sys::Module;
+
+mod common;
+
+#[test]
+fn test_render_structure_tree() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let value = context.eval("pe.sections[0]").unwrap();
+
+    let rendered = Renderer::default().render(&value);
+    let mut lines = rendered.lines();
+    assert_eq!(lines.next(), Some("structure"));
+    assert!(lines.next().unwrap().starts_with("  name: \""));
+}
+
+#[test]
+fn test_render_integer_in_hex() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let value = context.eval("pe.number_of_sections").unwrap();
+
+    assert_eq!(Renderer::default().render(&value), "4 (0x4)");
+}
+
+#[test]
+fn test_render_dictionary() {
+    let mut context = common::context_with_pe_signed_sample();
+    let value = context.eval("pe.version_info").unwrap();
+
+    let rendered = Renderer::default().render(&value);
+    assert!(rendered.starts_with("dictionary["));
+    assert!(rendered.contains("\n  \"OriginalFilename\": \"Demo.EXE\""));
+}
+
+#[test]
+fn test_render_truncated_array() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let value = context.eval("pe.sections").unwrap();
+
+    let renderer = Renderer {
+        max_items: 1,
+        ..Renderer::default()
+    };
+    let rendered = renderer.render(&value);
+    assert!(rendered.starts_with("array[4]\n  [0]: structure\n"));
+    assert!(!rendered.contains("[1]: "));
+    assert!(rendered.ends_with("\n  ... 3 more items"));
+
+    let rendered = renderer.with_full(true).render(&value);
+    assert!(rendered.contains("\n  [3]: structure\n"));
+    assert!(!rendered.contains("more items"));
+}
+
+#[test]
+fn test_render_color() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let value = context.eval("pe.number_of_sections").unwrap();
+
+    let rendered = Renderer::default().with_color(true).render(&value);
+    assert_eq!(rendered, "\x1b[36m4 (0x4)\x1b[0m");
+}
+
+#[test]
+fn test_render_module_node() {
+    let mut context = common::context_with_pe_sample_and_rule();
+    let dump = context.dump_module(Module::Pe).unwrap();
+
+    let rendered = Renderer::default().with_full(true).render_node(&dump);
+    assert!(rendered.starts_with("pe: structure\n"));
+    assert!(rendered.contains("\n  number_of_sections: 4 (0x4)\n"));
+    assert!(rendered.contains("\n  sections: array[4]\n"));
+    assert!(rendered.contains("\n  exports: function "));
+}
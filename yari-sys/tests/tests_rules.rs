@@ -0,0 +1,171 @@
+extern crate yari_sys;
+
+mod common;
+
+use indexmap::IndexMap;
+use yari_sys::rules::{MetaValue, RuleString};
+use yari_sys::{ContextBuilder, StringMatch, YariError, YrValue, YR_UNDEFINED};
+
+#[test]
+fn test_rules() {
+    let context = common::context_with_introspected_rules();
+    let names: Vec<String> = context.rules().iter().map(|r| r.qualified_name()).collect();
+    assert_eq!(
+        names,
+        vec!["default:PRIVATE", "default:GLOBAL", "default:r", "other:r"]
+    );
+}
+
+#[test]
+fn test_rule_flags_tags_and_metas() {
+    let context = common::context_with_introspected_rules();
+
+    let rule = context.rule("PRIVATE").unwrap();
+    assert!(rule.is_private);
+    assert!(!rule.is_global);
+    assert_eq!(rule.tags, vec!["tag1", "tag2"]);
+    assert_eq!(
+        rule.metas,
+        vec![
+            ("author".to_string(), MetaValue::String("test".to_string())),
+            ("version".to_string(), MetaValue::Integer(2)),
+            ("enabled".to_string(), MetaValue::Boolean(true)),
+        ]
+    );
+
+    let rule = context.rule("GLOBAL").unwrap();
+    assert!(!rule.is_private);
+    assert!(rule.is_global);
+    assert!(rule.tags.is_empty());
+    assert!(rule.metas.is_empty());
+}
+
+#[test]
+fn test_rule_strings() {
+    let context = common::context_with_introspected_rules();
+
+    let rule = context.rule("default:r").unwrap();
+    assert_eq!(
+        rule.strings,
+        vec![
+            RuleString {
+                identifier: "$s00".to_string(),
+                modifiers: vec!["ascii".to_string(), "wide".to_string()],
+                matches: 1,
+            },
+            RuleString {
+                identifier: "$s01".to_string(),
+                modifiers: vec!["nocase".to_string(), "fullword".to_string()],
+                matches: 1,
+            },
+            RuleString {
+                identifier: "$[1]".to_string(),
+                modifiers: vec![],
+                matches: 2,
+            },
+        ]
+    );
+
+    let rule = context.rule("other:r").unwrap();
+    assert!(rule.strings.is_empty());
+    assert!(context.rule("missing").is_none());
+    assert!(context.rule("missing:r").is_none());
+}
+
+#[test]
+fn test_imports() {
+    assert_eq!(
+        common::context_with_introspected_rules().imports(),
+        vec!["math", "pe"]
+    );
+    assert!(ContextBuilder::default()
+        .build()
+        .unwrap()
+        .rules()
+        .is_empty());
+}
+
+#[test]
+fn test_string_matches() {
+    let mut context = common::context_with_string_matches();
+
+    assert_eq!(
+        context.string_matches("r", "$s00"),
+        Ok(vec![
+            StringMatch {
+                base: 0,
+                offset: 0,
+                length: 5,
+                data: b"Hello".to_vec(),
+                xor_key: None,
+                chain_length: 0,
+            },
+            StringMatch {
+                base: 0,
+                offset: 12,
+                length: 5,
+                data: b"Hello".to_vec(),
+                xor_key: None,
+                chain_length: 0,
+            },
+        ])
+    );
+    assert_eq!(
+        context.string_matches("default:r", "s01"),
+        Ok(vec![StringMatch {
+            base: 0,
+            offset: 6,
+            length: 5,
+            data: b"WORLD".to_vec(),
+            xor_key: Some(0x20),
+            chain_length: 0,
+        }])
+    );
+    assert_eq!(context.string_matches("r", "$s02"), Ok(vec![]));
+    assert_eq!(
+        context.string_matches("r", "$s03"),
+        Err(YariError::UndeclaredStringError)
+    );
+    assert_eq!(
+        context.string_matches("r", "$s*"),
+        Err(YariError::UndeclaredStringError)
+    );
+    assert_eq!(
+        context.string_matches("missing", "$s00"),
+        Err(YariError::RuleMissingError)
+    );
+}
+
+#[test]
+fn test_eval_string_matches() {
+    let mut context = common::context_with_string_matches();
+
+    let YrValue::Array(matches) = context.eval("r|matches($s00)").unwrap() else {
+        panic!("array expected");
+    };
+    assert_eq!(matches.len(), 2);
+    assert_eq!(
+        matches[1],
+        YrValue::Structure(Some(IndexMap::from([
+            ("base".to_string(), YrValue::Integer(0)),
+            ("offset".to_string(), YrValue::Integer(12)),
+            ("length".to_string(), YrValue::Integer(5)),
+            (
+                "data".to_string(),
+                YrValue::String(Some("Hello".to_string()))
+            ),
+            ("xor_key".to_string(), YrValue::Integer(YR_UNDEFINED)),
+            ("chain_length".to_string(), YrValue::Integer(0)),
+        ])))
+    );
+
+    assert_eq!(context.eval("r|matches($s02)"), Ok(YrValue::Array(vec![])));
+    assert_eq!(
+        context.eval("r|matches($s*)"),
+        Err(YariError::UndeclaredStringError)
+    );
+    assert_eq!(
+        context.eval("matches($s00)"),
+        Err(YariError::RuleMissingError)
+    );
+}
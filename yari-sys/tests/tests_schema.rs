@@ -0,0 +1,72 @@
+use yari_sys::dump::ObjectKind;
+use yari_sys::Module;
+use yari_sys::MODULES;
+
+mod common;
+
+#[test]
+fn test_schema_all_modules() {
+    let mut context = common::context();
+
+    for module in MODULES {
+        let schema = context.module_schema(*module).unwrap();
+        assert_eq!(schema.kind, ObjectKind::Structure);
+        assert_eq!(schema.name, module.to_string());
+    }
+}
+
+#[test]
+fn test_schema_pe_without_sample() {
+    let mut context = common::context();
+    let schema = context.module_schema(Module::Pe).unwrap();
+
+    let sections = schema.member("sections").unwrap();
+    assert_eq!(sections.kind, ObjectKind::Array);
+    let section = sections.item.as_ref().unwrap();
+    assert_eq!(section.kind, ObjectKind::Structure);
+    assert_eq!(section.member("name").unwrap().kind, ObjectKind::String);
+    assert_eq!(
+        section.member("virtual_address").unwrap().kind,
+        ObjectKind::Integer
+    );
+
+    let version_info = schema.member("version_info").unwrap();
+    assert_eq!(version_info.kind, ObjectKind::Dictionary);
+    assert_eq!(version_info.item.as_ref().unwrap().kind, ObjectKind::String);
+
+    let exports = schema.member("exports").unwrap();
+    assert_eq!(exports.kind, ObjectKind::Function);
+    assert!(exports.prototypes.contains(&"s".to_string()));
+    assert!(exports.prototypes.contains(&"i".to_string()));
+    assert_eq!(exports.return_kind, Some(ObjectKind::Integer));
+}
+
+#[test]
+fn test_schema_does_not_depend_on_sample() {
+    let mut plain = common::context();
+    let mut with_sample = common::context_with_pe_sample_and_rule();
+
+    assert_eq!(
+        plain.module_schema(Module::Pe).unwrap(),
+        with_sample.module_schema(Module::Pe).unwrap()
+    );
+}
+
+#[test]
+fn test_schema_json() {
+    let mut context = common::context();
+    let schema = context.module_schema(Module::Math).unwrap();
+
+    let json = serde_json::to_value(&schema).unwrap();
+    assert_eq!(json["name"], "math");
+    assert_eq!(json["kind"], "structure");
+    let entropy = json["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["name"] == "entropy")
+        .unwrap();
+    assert_eq!(entropy["kind"], "function");
+    assert_eq!(entropy["prototypes"], serde_json::json!(["ii", "s"]));
+    assert_eq!(entropy["return_type"], "float");
+}